@@ -0,0 +1,32 @@
+use bigspider_accumulator::{bench_elements, Accumulator, SimpleAccumulator, SimpleProver};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Insert throughput over a fixed pseudorandom input, for the accumulator
+/// alone and for a prover keeping full history
+fn insert(c: &mut Criterion) {
+    let elements = bench_elements::<sha2::Sha256>(1, 1 << 12);
+
+    let mut group = c.benchmark_group("insert");
+    group.bench_function("accumulator", |b| {
+        b.iter(|| {
+            let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+            for e in &elements {
+                acc.insert(e);
+            }
+            black_box(acc.get_root())
+        })
+    });
+    group.bench_function("prover", |b| {
+        b.iter(|| {
+            let mut prover = SimpleProver::<sha2::Sha256>::default();
+            for e in &elements {
+                prover.insert(e);
+            }
+            black_box(prover.get_root())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert);
+criterion_main!(benches);
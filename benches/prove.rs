@@ -0,0 +1,90 @@
+use bigspider_accumulator::{Accumulator, Prover, SimpleProver};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use digest::Digest;
+
+fn prover(n: u32) -> SimpleProver<sha2::Sha256> {
+    (0..n).map(|i| sha2::Sha256::digest(&i.to_be_bytes())).collect()
+}
+
+/// Proving every element against the latest root, allocating a fresh witness
+/// per proof versus reusing a single buffer. Run with and without the `fxhash`
+/// feature to compare history map types.
+fn prove_all(c: &mut Criterion) {
+    let prover = prover(1 << 12);
+    let len = prover.len().as_u64();
+
+    let mut group = c.benchmark_group("prove_all");
+    group.bench_function("prove_from", |b| {
+        b.iter(|| {
+            for j in 1..=len {
+                black_box(prover.prove_from(len, j).unwrap());
+            }
+        })
+    });
+    group.bench_function("prove_into", |b| {
+        let mut buf = Vec::new();
+        b.iter(|| {
+            for j in 1..=len {
+                buf.clear();
+                prover.prove_into(len, j, &mut buf).unwrap();
+                black_box(&buf);
+            }
+        })
+    });
+    group.finish();
+}
+
+/// Loading a prover from scratch, with and without preallocating its
+/// history. Preallocation only applies with the `fxhash` feature.
+fn bulk_insert(c: &mut Criterion) {
+    const N: usize = 1 << 12;
+    let elements: Vec<_> = (0..N as u32)
+        .map(|i| sha2::Sha256::digest(&i.to_be_bytes()))
+        .collect();
+
+    let mut group = c.benchmark_group("bulk_insert");
+    group.bench_function("default", |b| {
+        b.iter(|| {
+            let mut prover = SimpleProver::<sha2::Sha256>::default();
+            prover.extend(elements.iter().copied());
+            black_box(prover)
+        })
+    });
+    group.bench_function("with_capacity", |b| {
+        b.iter(|| {
+            let mut prover = SimpleProver::<sha2::Sha256>::with_capacity(N);
+            prover.extend(elements.iter().copied());
+            black_box(prover)
+        })
+    });
+    group.finish();
+}
+
+/// Verifying every element against the latest root, for a 32-byte and a
+/// 64-byte digest. The walk borrows every node from the witness, so the gap
+/// between the two should be the extra hashing alone.
+fn verify_all(c: &mut Criterion) {
+    fn bench<D: Digest + Clone + Default>(c: &mut Criterion, name: &str) {
+        let prover: SimpleProver<D> = (0..1u32 << 12)
+            .map(|i| D::digest(&i.to_be_bytes()))
+            .collect();
+        let len = prover.len();
+        let root = prover.get_root();
+        let proofs: Vec<_> = (1..=len.as_u64())
+            .map(|j| (j.into(), prover.prove_with_element(len, j).unwrap()))
+            .collect();
+
+        c.benchmark_group("verify_all").bench_function(name, |b| {
+            b.iter(|| {
+                for (j, (element, witness)) in &proofs {
+                    SimpleProver::<D>::verify(&root, len, *j, witness, element).unwrap();
+                }
+            })
+        });
+    }
+    bench::<sha2::Sha256>(c, "sha256");
+    bench::<sha2::Sha512>(c, "sha512");
+}
+
+criterion_group!(benches, prove_all, bulk_insert, verify_all);
+criterion_main!(benches);
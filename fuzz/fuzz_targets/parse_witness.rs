@@ -0,0 +1,18 @@
+//! Every byte parser must return `Err` on bad input, never panic
+
+#![no_main]
+use bigspider_accumulator::{parse_canonical_witness, verify_dyn, Bloom, DigestKind};
+use ethers_core::types::U256;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_canonical_witness::<sha2::Sha256, U256>(data);
+    let _ = parse_canonical_witness::<sha2::Sha256, u64>(data);
+    let _ = parse_canonical_witness::<sha2::Sha512, U256>(data);
+    let _ = Bloom::from_bytes(data);
+
+    if let Ok((i, j, nodes)) = parse_canonical_witness::<sha2::Sha256, U256>(data) {
+        let witness = nodes.concat();
+        let _ = verify_dyn(DigestKind::Sha256, &nodes[0], i, j, &witness, &nodes[0]);
+    }
+});
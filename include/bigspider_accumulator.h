@@ -0,0 +1,89 @@
+#ifndef BIGSPIDER_ACCUMULATOR_H
+#define BIGSPIDER_ACCUMULATOR_H
+
+/* Generated by cbindgen from src/capi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The proof verified
+ */
+#define ACC_OK 0
+
+/**
+ * A required pointer was null
+ */
+#define ACC_ERR_NULL 1
+
+/**
+ * The witness length is not a multiple of 32 bytes
+ */
+#define ACC_ERR_LENGTH 2
+
+/**
+ * `j` is 0 or greater than `i`
+ */
+#define ACC_ERR_OUT_OF_BOUNDS 3
+
+/**
+ * The witness ends before reaching `j`
+ */
+#define ACC_ERR_WITNESS_TOO_SHORT 4
+
+/**
+ * The witness has nodes after those the proof needs
+ */
+#define ACC_ERR_WITNESS_TOO_LONG 5
+
+/**
+ * A triple of the witness does not hash to the root expected there
+ */
+#define ACC_ERR_ROOT_MISMATCH 6
+
+/**
+ * The witness commits to a different element at `j`
+ */
+#define ACC_ERR_ELEMENT_MISMATCH 7
+
+/**
+ * The witness leads to the empty root at an index other than 0
+ */
+#define ACC_ERR_EMPTY_ROOT 8
+
+/**
+ * Any other failure
+ */
+#define ACC_ERR_OTHER 255
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Verify that `witness` proves `element` at index `j` against `root`, the
+ * root at index `i`. This is
+ * [`Prover::verify`](crate::Prover::verify) for a SHA-256 log.
+ *
+ * # Safety
+ *
+ * `root` and `element` must each point to 32 readable bytes, and `witness`
+ * to `witness_len` readable bytes. `witness` may be null if `witness_len`
+ * is 0.
+ */
+int32_t acc_verify_sha256(const uint8_t *root,
+                          uint64_t i_lo,
+                          uint64_t i_hi,
+                          uint64_t j_lo,
+                          uint64_t j_hi,
+                          const uint8_t *witness,
+                          uintptr_t witness_len,
+                          const uint8_t *element);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* BIGSPIDER_ACCUMULATOR_H */
@@ -0,0 +1,24 @@
+//! Reproducible inputs for benchmarks
+
+use digest::{Digest, Output};
+
+/// `n` pseudorandom elements generated from `seed`. Each element is the
+/// digest of the next output of a xorshift64 generator, so a seed gives the
+/// same elements on every run and platform. This is for benchmarks only: the
+/// elements are not unpredictable.
+pub fn bench_elements<D: Digest>(seed: u64, n: usize) -> Vec<Output<D>> {
+    // 0 is a fixed point of xorshift
+    let mut state = if seed == 0 {
+        0x9e37_79b9_7f4a_7c15
+    } else {
+        seed
+    };
+    (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            D::digest(&state.to_be_bytes())
+        })
+        .collect()
+}
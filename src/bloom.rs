@@ -0,0 +1,102 @@
+//! A Bloom filter over inserted elements, for a quick check of whether an
+//! element could be in the log before asking for a proof.
+//!
+//! Elements are digest outputs, so they are already uniformly distributed
+//! and the filter takes its bit positions from the element bytes directly,
+//! by double hashing, rather than hashing them again.
+
+use crate::ProverError;
+
+/// A Bloom filter over element bytes. [`Bloom::might_contain`] never returns
+/// `false` for an inserted element; it returns `true` for other elements
+/// with a probability that grows with the number inserted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bloom {
+    words: Vec<u64>,
+    hashes: u32,
+}
+
+impl Default for Bloom {
+    /// 2^16 bits and 4 positions per element: about one false positive in
+    /// 80,000 after 1,000 inserts, and one in 23 after 10,000
+    fn default() -> Self {
+        Self::new(1 << 10, 4)
+    }
+}
+
+impl Bloom {
+    /// The most bit positions per element a filter may use. More is never
+    /// useful, and would let a bad encoding make every check arbitrarily
+    /// slow.
+    pub const MAX_HASHES: u32 = 64;
+
+    /// An empty filter of `words` 64-bit words, setting `hashes` bits per
+    /// element. Both are raised to at least 1, and `hashes` is capped at
+    /// [`Bloom::MAX_HASHES`].
+    pub fn new(words: usize, hashes: u32) -> Self {
+        Self {
+            words: vec![0; words.max(1)],
+            hashes: hashes.clamp(1, Self::MAX_HASHES),
+        }
+    }
+
+    fn positions(&self, element: &[u8]) -> impl Iterator<Item = usize> {
+        let mut h = [0u64; 2];
+        for (n, b) in element.iter().enumerate() {
+            h[(n / 8) % 2] ^= (*b as u64) << (8 * (n % 8));
+        }
+        let bits = self.words.len() as u64 * 64;
+        let (h1, h2) = (h[0], h[1] | 1);
+        (0..self.hashes as u64).map(move |n| (h1.wrapping_add(n.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    /// Record `element`
+    pub fn insert(&mut self, element: &[u8]) {
+        for p in self.positions(element).collect::<Vec<_>>() {
+            self.words[p / 64] |= 1 << (p % 64);
+        }
+    }
+
+    /// `false` if `element` was definitely never inserted. `true` if it may
+    /// have been, which only a proof can confirm.
+    pub fn might_contain(&self, element: &[u8]) -> bool {
+        self.positions(element)
+            .all(|p| self.words[p / 64] & (1 << (p % 64)) != 0)
+    }
+
+    /// Encode the filter for storage: the hash count as 4 big-endian bytes,
+    /// then each word as 8 big-endian bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + 8 * self.words.len());
+        out.extend_from_slice(&self.hashes.to_be_bytes());
+        for w in &self.words {
+            out.extend_from_slice(&w.to_be_bytes());
+        }
+        out
+    }
+
+    /// Decode a filter encoded by [`Bloom::to_bytes`]. Fails with
+    /// `InconsistentState` if the length is invalid, or the hash count is 0
+    /// or over [`Bloom::MAX_HASHES`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProverError> {
+        let invalid = ProverError::InconsistentState("invalid bloom filter");
+        if bytes.len() < 12 || !bytes[4..].chunks_exact(8).remainder().is_empty() {
+            return Err(invalid);
+        }
+        let mut hashes = [0u8; 4];
+        hashes.copy_from_slice(&bytes[..4]);
+        let hashes = u32::from_be_bytes(hashes);
+        if hashes == 0 || hashes > Self::MAX_HASHES {
+            return Err(invalid);
+        }
+        let words = bytes[4..]
+            .chunks_exact(8)
+            .map(|c| {
+                let mut w = [0u8; 8];
+                w.copy_from_slice(c);
+                u64::from_be_bytes(w)
+            })
+            .collect();
+        Ok(Self { words, hashes })
+    }
+}
@@ -0,0 +1,110 @@
+//! A C ABI for verifying SHA-256 proofs from other languages.
+//!
+//! Build a library with the `capi` feature, for example with
+//! `cargo rustc --release --features capi --crate-type cdylib`, and include
+//! `include/bigspider_accumulator.h`. The header is generated from this
+//! module with `cbindgen --config cbindgen.toml --output
+//! include/bigspider_accumulator.h`.
+//!
+//! Roots and elements are 32 bytes. Indices are 128 bits, passed as their
+//! low and high 64-bit halves. A witness is its nodes concatenated, so its
+//! length in bytes is a multiple of 32. Every function returns [`ACC_OK`]
+//! on success, and one of the nonzero `ACC_ERR_*` codes otherwise. Nothing
+//! is allocated that the caller must free, and no pointer is kept after a
+//! call returns.
+
+use std::panic;
+use std::slice;
+
+use digest::Output;
+use ethers_core::types::U256;
+use sha2::Sha256;
+
+use crate::{Prover, ProverError, SimpleProver};
+
+/// The proof verified
+pub const ACC_OK: i32 = 0;
+/// A required pointer was null
+pub const ACC_ERR_NULL: i32 = 1;
+/// The witness length is not a multiple of 32 bytes
+pub const ACC_ERR_LENGTH: i32 = 2;
+/// `j` is 0 or greater than `i`
+pub const ACC_ERR_OUT_OF_BOUNDS: i32 = 3;
+/// The witness ends before reaching `j`
+pub const ACC_ERR_WITNESS_TOO_SHORT: i32 = 4;
+/// The witness has nodes after those the proof needs
+pub const ACC_ERR_WITNESS_TOO_LONG: i32 = 5;
+/// A triple of the witness does not hash to the root expected there
+pub const ACC_ERR_ROOT_MISMATCH: i32 = 6;
+/// The witness commits to a different element at `j`
+pub const ACC_ERR_ELEMENT_MISMATCH: i32 = 7;
+/// The witness leads to the empty root at an index other than 0
+pub const ACC_ERR_EMPTY_ROOT: i32 = 8;
+/// Any other failure
+pub const ACC_ERR_OTHER: i32 = 255;
+
+const NODE: usize = 32;
+
+fn index(lo: u64, hi: u64) -> U256 {
+    U256::from((hi as u128) << 64 | lo as u128)
+}
+
+fn code(e: ProverError) -> i32 {
+    match e {
+        ProverError::OutOfBounds => ACC_ERR_OUT_OF_BOUNDS,
+        ProverError::WitnessTooShort => ACC_ERR_WITNESS_TOO_SHORT,
+        ProverError::WitnessTooLong => ACC_ERR_WITNESS_TOO_LONG,
+        ProverError::RiMismatch(_) => ACC_ERR_ROOT_MISMATCH,
+        ProverError::XiMismatch => ACC_ERR_ELEMENT_MISMATCH,
+        ProverError::UnexpectedEmptyRoot => ACC_ERR_EMPTY_ROOT,
+        _ => ACC_ERR_OTHER,
+    }
+}
+
+/// Verify that `witness` proves `element` at index `j` against `root`, the
+/// root at index `i`. This is
+/// [`Prover::verify`](crate::Prover::verify) for a SHA-256 log.
+///
+/// # Safety
+///
+/// `root` and `element` must each point to 32 readable bytes, and `witness`
+/// to `witness_len` readable bytes. `witness` may be null if `witness_len`
+/// is 0.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn acc_verify_sha256(
+    root: *const u8,
+    i_lo: u64,
+    i_hi: u64,
+    j_lo: u64,
+    j_hi: u64,
+    witness: *const u8,
+    witness_len: usize,
+    element: *const u8,
+) -> i32 {
+    if root.is_null() || element.is_null() || (witness.is_null() && witness_len != 0) {
+        return ACC_ERR_NULL;
+    }
+    let witness: &[u8] = if witness_len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(witness, witness_len)
+    };
+    let nodes = witness.chunks_exact(NODE);
+    if !nodes.remainder().is_empty() {
+        return ACC_ERR_LENGTH;
+    }
+    let root = Output::<Sha256>::clone_from_slice(slice::from_raw_parts(root, NODE));
+    let element = Output::<Sha256>::clone_from_slice(slice::from_raw_parts(element, NODE));
+    let nodes: Vec<_> = nodes.map(Output::<Sha256>::clone_from_slice).collect();
+    let (i, j) = (index(i_lo, i_hi), index(j_lo, j_hi));
+
+    // A panic must not unwind into the caller
+    let result =
+        panic::catch_unwind(|| SimpleProver::<Sha256>::verify(&root, i, j, &nodes, &element));
+    match result {
+        Ok(Ok(())) => ACC_OK,
+        Ok(Err(e)) => code(e),
+        Err(_) => ACC_ERR_OTHER,
+    }
+}
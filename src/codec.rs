@@ -0,0 +1,102 @@
+//! A canonical byte encoding for witnesses.
+//!
+//! The encoding is `i || j || nodes`, where `i` and `j` are 32-byte
+//! big-endian integers and each node is a digest output, concatenated in
+//! witness order. A [`WitnessEnvelope`] puts a header naming the digest in
+//! front of it.
+
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+
+use crate::{utils, Index, NamedDigest, ProverError};
+
+/// Encode a witness proving `j` against `i`
+pub fn encode_canonical_witness<D: Digest, I: Index>(i: I, j: I, nodes: &[Output<D>]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64 + nodes.len() * D::output_size());
+    for n in [i, j].iter() {
+        let mut buf = [0u8; 32];
+        (*n).into().to_big_endian(&mut buf);
+        out.extend_from_slice(&buf);
+    }
+    for node in nodes {
+        out.extend_from_slice(node);
+    }
+    out
+}
+
+/// Parse a witness encoded by [`encode_canonical_witness`], returning `i`,
+/// `j` and the nodes. Fails with `MalformedWitness` unless:
+///
+/// - the length is 64 bytes plus a whole number of nodes
+/// - `i` and `j` fit in `I`
+/// - `0 < j <= i`
+/// - there are exactly `3 * proof_steps(i, j)` nodes, as `prove_from(i, j)`
+///   would produce
+pub fn parse_canonical_witness<D: Digest, I: Index>(
+    bytes: &[u8],
+) -> Result<(I, I, Vec<Output<D>>), ProverError> {
+    if bytes.len() < 64 {
+        return Err(ProverError::MalformedWitness);
+    }
+    let chunks = bytes[64..].chunks_exact(D::output_size());
+    if !chunks.remainder().is_empty() {
+        return Err(ProverError::MalformedWitness);
+    }
+    let index =
+        |b: &[u8]| I::from_u256(U256::from_big_endian(b)).ok_or(ProverError::MalformedWitness);
+    let (i, j) = (index(&bytes[..32])?, index(&bytes[32..64])?);
+    if j.is_zero() || j > i {
+        return Err(ProverError::MalformedWitness);
+    }
+
+    // checked before collecting, so that an oversized input is rejected
+    // without allocating for it
+    if chunks.len() != 3 * utils::proof_steps(i, j) {
+        return Err(ProverError::MalformedWitness);
+    }
+    Ok((i, j, chunks.map(Output::<D>::clone_from_slice).collect()))
+}
+
+/// A canonically encoded witness with a header naming the digest that
+/// produced it: one byte giving the length of [`NamedDigest::NAME`], the
+/// name, then the encoding of [`encode_canonical_witness`].
+#[derive(Clone, Debug)]
+pub struct WitnessEnvelope<D: Digest, I = U256> {
+    /// The index of the root the witness proves against
+    pub i: I,
+    /// The index of the proven element
+    pub j: I,
+    /// The witness
+    pub nodes: Vec<Output<D>>,
+}
+
+impl<D: NamedDigest, I: Index> WitnessEnvelope<D, I> {
+    /// Encode the envelope, with the name of `D` in the header
+    pub fn encode(&self) -> Vec<u8> {
+        let name = D::NAME.as_bytes();
+        let mut out = vec![name.len() as u8];
+        out.extend_from_slice(name);
+        out.extend(encode_canonical_witness::<D, I>(
+            self.i,
+            self.j,
+            &self.nodes,
+        ));
+        out
+    }
+
+    /// Decode an envelope written by [`WitnessEnvelope::encode`]. Fails with
+    /// `DigestMismatch` if the header names a digest other than `D`, and
+    /// otherwise as [`parse_canonical_witness`] does.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ProverError> {
+        let (len, rest) = bytes.split_first().ok_or(ProverError::MalformedWitness)?;
+        if rest.len() < *len as usize {
+            return Err(ProverError::MalformedWitness);
+        }
+        let (name, witness) = rest.split_at(*len as usize);
+        if name != D::NAME.as_bytes() {
+            return Err(ProverError::DigestMismatch);
+        }
+        let (i, j, nodes) = parse_canonical_witness::<D, I>(witness)?;
+        Ok(Self { i, j, nodes })
+    }
+}
@@ -0,0 +1,107 @@
+//! Machine-readable descriptions of the proof formats, for clients in other
+//! languages
+
+use ethers_core::types::U256;
+use serde_json::{json, Value};
+
+use crate::{expected_witness_len, ProverError};
+
+/// A JSON Schema (draft 07) for a serialized [`ProofBundle`] over a 32-byte
+/// digest with `U256` indices. Digest outputs are lowercase hex without a
+/// prefix, and indices are `0x`-prefixed hex quantities.
+///
+/// [`ProofBundle`]: crate::ProofBundle
+pub fn proof_json_schema() -> Value {
+    proof_json_schema_for(32)
+}
+
+/// [`proof_json_schema`] for a digest with `output_size`-byte outputs, such
+/// as an accumulator's `OUTPUT_SIZE`
+pub fn proof_json_schema_for(output_size: usize) -> Value {
+    let node = format!("^[0-9a-f]{{{}}}$", 2 * output_size);
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "ProofBundle",
+        "type": "object",
+        "required": ["root", "genesis", "i", "j", "element", "witness"],
+        "additionalProperties": false,
+        "properties": {
+            "root": { "$ref": "#/definitions/node" },
+            "genesis": { "$ref": "#/definitions/node" },
+            "i": { "$ref": "#/definitions/index" },
+            "j": { "$ref": "#/definitions/index" },
+            "element": { "$ref": "#/definitions/node" },
+            "witness": {
+                "type": "array",
+                "items": { "$ref": "#/definitions/node" }
+            }
+        },
+        "definitions": {
+            "node": {
+                "type": "string",
+                "pattern": node
+            },
+            "index": {
+                "type": "string",
+                "pattern": "^0x(0|[1-9a-f][0-9a-f]{0,63})$"
+            }
+        }
+    })
+}
+
+/// Check that `value` matches [`proof_json_schema`], and that the witness
+/// has the number of nodes its indices call for, which the schema can't
+/// express. Fails with `MalformedWitness`. This checks structure only; the
+/// proof itself is not verified.
+pub fn validate_proof_json(value: &Value) -> Result<(), ProverError> {
+    validate_proof_json_for(value, 32)
+}
+
+/// [`validate_proof_json`] against [`proof_json_schema_for`] `output_size`
+pub fn validate_proof_json_for(value: &Value, output_size: usize) -> Result<(), ProverError> {
+    let object = value.as_object().ok_or(ProverError::MalformedWitness)?;
+    const FIELDS: [&str; 6] = ["root", "genesis", "i", "j", "element", "witness"];
+    if object.len() != FIELDS.len() || !FIELDS.iter().all(|f| object.contains_key(f)) {
+        return Err(ProverError::MalformedWitness);
+    }
+
+    let witness = value["witness"]
+        .as_array()
+        .ok_or(ProverError::MalformedWitness)?;
+    let nodes = [&value["root"], &value["genesis"], &value["element"]];
+    if !nodes
+        .iter()
+        .copied()
+        .chain(witness)
+        .all(|n| is_node(n, output_size))
+    {
+        return Err(ProverError::MalformedWitness);
+    }
+
+    let (i, j) = (index(&value["i"])?, index(&value["j"])?);
+    if expected_witness_len(i, j).map_err(|_| ProverError::MalformedWitness)? != witness.len() {
+        return Err(ProverError::MalformedWitness);
+    }
+    Ok(())
+}
+
+fn is_node(value: &Value, output_size: usize) -> bool {
+    match value.as_str() {
+        Some(s) => {
+            s.len() == 2 * output_size && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        }
+        None => false,
+    }
+}
+
+fn index(value: &Value) -> Result<U256, ProverError> {
+    let hex = value
+        .as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .ok_or(ProverError::MalformedWitness)?;
+    let canonical = hex == "0" || (!hex.starts_with('0') && hex.len() <= 64);
+    if !canonical || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return Err(ProverError::MalformedWitness);
+    }
+    serde_json::from_value(value.clone()).map_err(|_| ProverError::MalformedWitness)
+}
@@ -0,0 +1,80 @@
+//! Witnesses with their recomputable nodes removed.
+//!
+//! Every triple but the last hashes to the root the walk follows out of the
+//! triple before it, so that root can be dropped and recomputed. A compact
+//! witness keeps `x` and the root not followed for each of those triples,
+//! and the last triple whole: `2 * steps + 1` nodes instead of `3 * steps`.
+
+use digest::{Digest, Output};
+
+use crate::{utils, verify, Branch, Index, ProverError};
+
+/// A witness with the roots the verifier can recompute removed. See
+/// [`Prover::shrink_witness`](crate::Prover::shrink_witness).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactWitness<D: Digest> {
+    /// `x` and the unfollowed root of each triple but the last, then the
+    /// last triple
+    pub nodes: Vec<Output<D>>,
+}
+
+/// Drop the followed root from every triple but the last
+pub(crate) fn shrink<D: Digest, I: Index>(
+    i: I,
+    j: I,
+    witness: &[Output<D>],
+) -> Result<CompactWitness<D>, ProverError> {
+    let branches = utils::branches(i, j);
+    check_len(witness.len(), 3 * (branches.len() + 1))?;
+
+    let mut nodes = Vec::with_capacity(2 * branches.len() + 3);
+    for (triple, branch) in witness.chunks_exact(3).zip(&branches) {
+        let kept = match branch {
+            Branch::Pred => &triple[1],
+            Branch::Prev => &triple[2],
+        };
+        nodes.push(triple[0].clone());
+        nodes.push(kept.clone());
+    }
+    nodes.extend_from_slice(&witness[witness.len() - 3..]);
+    Ok(CompactWitness { nodes })
+}
+
+/// Rebuild the full witness, recomputing each dropped root from the triple
+/// after it
+pub(crate) fn expand<D: Digest, I: Index>(
+    i: I,
+    j: I,
+    compact: &CompactWitness<D>,
+) -> Result<Vec<Output<D>>, ProverError> {
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    let branches = utils::branches(i, j);
+    let nodes = &compact.nodes;
+    check_len(nodes.len(), 2 * branches.len() + 3)?;
+
+    let mut witness = vec![Output::<D>::default(); 3 * (branches.len() + 1)];
+    let last = witness.len() - 3;
+    witness[last..].clone_from_slice(&nodes[nodes.len() - 3..]);
+    for (n, branch) in branches.iter().enumerate().rev() {
+        let followed = verify::hash_triple::<D>(
+            verify::triple_at::<D>(&witness, 3 * (n + 1)).ok_or(ProverError::WitnessTooShort)?,
+        );
+        let (x, kept) = (nodes[2 * n].clone(), nodes[2 * n + 1].clone());
+        let triple = match branch {
+            Branch::Pred => [x, kept, followed],
+            Branch::Prev => [x, followed, kept],
+        };
+        witness[3 * n..3 * n + 3].clone_from_slice(&triple);
+    }
+    Ok(witness)
+}
+
+fn check_len(len: usize, expected: usize) -> Result<(), ProverError> {
+    match len {
+        _ if len < expected => Err(ProverError::WitnessTooShort),
+        _ if len > expected => Err(ProverError::WitnessTooLong),
+        _ => Ok(()),
+    }
+}
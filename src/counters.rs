@@ -0,0 +1,38 @@
+//! Counters emitted through the [`metrics`](https://docs.rs/metrics) facade
+//! when the `metrics` feature is enabled. Without the feature the hooks are
+//! empty and compile away.
+
+/// Elements inserted, into either an accumulator or a prover
+pub const INSERTS: &str = "accumulator_inserts_total";
+/// Witnesses produced by `prove_from` and friends
+pub const PROOFS: &str = "accumulator_proofs_total";
+/// Total size in bytes of all witnesses produced
+pub const WITNESS_BYTES: &str = "accumulator_witness_bytes_total";
+/// Calls to `verify` that accepted the proof
+pub const VERIFY_SUCCESSES: &str = "accumulator_verify_successes_total";
+/// Calls to `verify` that rejected the proof
+pub const VERIFY_FAILURES: &str = "accumulator_verify_failures_total";
+
+#[inline(always)]
+pub(crate) fn inserted() {
+    #[cfg(feature = "metrics")]
+    metrics::increment_counter!(INSERTS);
+}
+
+#[inline(always)]
+pub(crate) fn proved(_witness_bytes: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::increment_counter!(PROOFS);
+        metrics::counter!(WITNESS_BYTES, _witness_bytes as u64);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn verified<T, E>(_result: &Result<T, E>) {
+    #[cfg(feature = "metrics")]
+    match _result {
+        Ok(_) => metrics::increment_counter!(VERIFY_SUCCESSES),
+        Err(_) => metrics::increment_counter!(VERIFY_FAILURES),
+    }
+}
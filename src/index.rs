@@ -0,0 +1,82 @@
+use ethers_core::types::U256;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{Add, BitAnd, Not, Sub};
+
+/// An unsigned integer type used to index accumulator elements. Defaults to
+/// `U256` throughout; `u64` and `u128` are provided for logs that will never
+/// outgrow them.
+///
+/// Indices are converted to `U256` only when reporting errors.
+pub trait Index:
+    Copy
+    + Ord
+    + Hash
+    + Debug
+    + Display
+    + Default
+    + Into<U256>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + BitAnd<Output = Self>
+    + Not<Output = Self>
+{
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn trailing_zeros(&self) -> u32;
+
+    /// Convert from `U256`, or `None` if `n` does not fit
+    fn from_u256(n: U256) -> Option<Self>;
+}
+
+impl Index for U256 {
+    fn zero() -> Self {
+        U256::zero()
+    }
+
+    fn one() -> Self {
+        U256::one()
+    }
+
+    fn trailing_zeros(&self) -> u32 {
+        U256::trailing_zeros(self)
+    }
+
+    fn from_u256(n: U256) -> Option<Self> {
+        Some(n)
+    }
+}
+
+macro_rules! impl_index {
+    ($($t:ty),*) => {$(
+        impl Index for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn trailing_zeros(&self) -> u32 {
+                <$t>::trailing_zeros(*self)
+            }
+
+            fn from_u256(n: U256) -> Option<Self> {
+                if n.bits() <= <$t>::BITS as usize {
+                    Some(n.low_u128() as $t)
+                } else {
+                    None
+                }
+            }
+        }
+    )*};
+}
+
+impl_index!(u64, u128);
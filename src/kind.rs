@@ -0,0 +1,124 @@
+//! Verification with the digest chosen at runtime, for a service that checks
+//! proofs from accumulators over different digests through one entry point
+
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+
+use crate::{Prover, ProverError, SimpleProver};
+
+/// A digest with a fixed name, recorded with serialized roots and proofs so
+/// that a reader can check it has the matching type. Names are compared on
+/// decode, so they must not change between releases, and must be at most
+/// 255 bytes to fit a [`WitnessEnvelope`](crate::WitnessEnvelope) header.
+/// Digests without a name can't be exported at all.
+pub trait NamedDigest: Digest {
+    const NAME: &'static str;
+}
+
+macro_rules! impl_named_digest {
+    ($($t:ty => $name:literal),*) => {$(
+        impl NamedDigest for $t {
+            const NAME: &'static str = $name;
+        }
+    )*};
+}
+
+impl_named_digest!(
+    sha2::Sha224 => "sha224",
+    sha2::Sha256 => "sha256",
+    sha2::Sha384 => "sha384",
+    sha2::Sha512 => "sha512",
+    sha2::Sha512Trunc224 => "sha512/224",
+    sha2::Sha512Trunc256 => "sha512/256"
+);
+
+/// A digest [`verify_dyn`] can dispatch to. These are the `sha2` digests,
+/// which the crate already depends on; more may be added in minor releases.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DigestKind {
+    Sha256,
+    Sha512,
+    Sha512Trunc256,
+}
+
+impl DigestKind {
+    const ALL: [DigestKind; 3] = [
+        DigestKind::Sha256,
+        DigestKind::Sha512,
+        DigestKind::Sha512Trunc256,
+    ];
+
+    /// The kind of the digest `D`, if it is one of these
+    pub fn of<D: NamedDigest>() -> Option<Self> {
+        Self::from_name(D::NAME)
+    }
+
+    /// The kind named `name`, as returned by [`DigestKind::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|k| k.name() == name)
+    }
+
+    /// The digest's [`NamedDigest::NAME`]
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestKind::Sha256 => sha2::Sha256::NAME,
+            DigestKind::Sha512 => sha2::Sha512::NAME,
+            DigestKind::Sha512Trunc256 => sha2::Sha512Trunc256::NAME,
+        }
+    }
+
+    /// The size in bytes of the digest's outputs
+    pub fn output_size(self) -> usize {
+        match self {
+            DigestKind::Sha256 => sha2::Sha256::output_size(),
+            DigestKind::Sha512 => sha2::Sha512::output_size(),
+            DigestKind::Sha512Trunc256 => sha2::Sha512Trunc256::output_size(),
+        }
+    }
+}
+
+/// [`Prover::verify`] over the digest `kind`, with the root, the element and
+/// the witness as bytes. The witness is its nodes concatenated, as in
+/// [`encode_canonical_witness`](crate::encode_canonical_witness) without the
+/// indices. Fails with `InvalidElementLength` if the root or element is not
+/// one output long, and `MalformedWitness` if the witness is not a whole
+/// number of outputs.
+pub fn verify_dyn(
+    kind: DigestKind,
+    root: &[u8],
+    i: U256,
+    j: U256,
+    witness: &[u8],
+    element: &[u8],
+) -> Result<(), ProverError> {
+    match kind {
+        DigestKind::Sha256 => verify_bytes::<sha2::Sha256>(root, i, j, witness, element),
+        DigestKind::Sha512 => verify_bytes::<sha2::Sha512>(root, i, j, witness, element),
+        DigestKind::Sha512Trunc256 => {
+            verify_bytes::<sha2::Sha512Trunc256>(root, i, j, witness, element)
+        }
+    }
+}
+
+fn verify_bytes<D: Digest + Clone + Default>(
+    root: &[u8],
+    i: U256,
+    j: U256,
+    witness: &[u8],
+    element: &[u8],
+) -> Result<(), ProverError> {
+    let output = |bytes: &[u8]| {
+        if bytes.len() != D::output_size() {
+            return Err(ProverError::InvalidElementLength(bytes.len()));
+        }
+        Ok(Output::<D>::clone_from_slice(bytes))
+    };
+    let (root, element) = (output(root)?, output(element)?);
+    let chunks = witness.chunks_exact(D::output_size());
+    if !chunks.remainder().is_empty() {
+        return Err(ProverError::MalformedWitness);
+    }
+    let nodes: Vec<Output<D>> = chunks.map(Output::<D>::clone_from_slice).collect();
+    SimpleProver::<D>::verify(&root, i, j, &nodes, &element)
+}
@@ -1,28 +1,365 @@
+#[cfg(feature = "bench")]
+mod bench;
+#[cfg(feature = "bloom")]
+mod bloom;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod codec;
+#[cfg(feature = "serialize")]
+pub mod codegen;
+mod compact;
+pub mod counters;
+mod index;
+mod kind;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod path;
+mod prefix;
+mod proof_walk;
+mod records;
+#[cfg(feature = "serialize")]
+mod serde_with;
+mod signing;
 mod simple;
+mod store;
+mod tagged;
 mod utils;
+#[cfg(test)]
+mod vectors;
+mod verify;
 
-use digest::{generic_array::typenum::consts::U32 as TU32, Digest, Output};
+#[cfg(feature = "bench")]
+pub use bench::bench_elements;
+#[cfg(feature = "bloom")]
+pub use bloom::Bloom;
+pub use codec::{encode_canonical_witness, parse_canonical_witness, WitnessEnvelope};
+pub use compact::CompactWitness;
+pub use index::Index;
+pub use kind::{verify_dyn, DigestKind, NamedDigest};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapStore;
+pub use path::Direction;
+pub use prefix::{DigestPrefix, Prefixed};
+pub use proof_walk::ProofWalk;
+#[cfg(feature = "serialize")]
+pub use serde_with::{b64 as serde_b64, hex as serde_hex};
+pub use signing::{RootSink, SigningProver};
+pub use simple::{History, Parts, SimpleAccumulator, SimpleProver, SlotDiff, Txn};
+pub use store::{prove_from_stores, Entries, HistoryStore, Interned};
+pub use tagged::TaggedProver;
+pub use verify::{expected_witness_len, VerifyJob, LENIENT_TRAILING_NODES};
+
+use digest::{generic_array::typenum::Unsigned, Digest, Output};
 use ethers_core::types::U256;
 use std::collections::BTreeMap;
 
 pub type Element<D> = Output<<D as Accumulator>::Digest>;
 
+/// The link a proof follows from one index to the next
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Branch {
+    /// Skip back to `pred(i)`
+    Pred,
+    /// Step back to `i - 1`
+    Prev,
+}
+
+/// A proof of membership against a published checkpoint root, rather than
+/// against the latest root
+#[derive(Clone, Debug)]
+pub struct AnchoredProof<D: Digest, I = U256> {
+    /// The index of the checkpoint the proof is anchored at
+    pub checkpoint: I,
+    /// The witness proving the element against the checkpoint root
+    pub witness: Vec<Output<D>>,
+}
+
+/// A proof of every element in `lo..=hi` against one root. The witness is
+/// the proof of `hi`, followed by the triples for `hi - 1` down to `lo`,
+/// each of which hashes to the `prev` root of the triple before it.
+#[derive(Clone, Debug)]
+pub struct RangeProof<D: Digest, I = U256> {
+    /// The first index in the range
+    pub lo: I,
+    /// The last index in the range
+    pub hi: I,
+    /// The proof of `hi`, then one triple per remaining element
+    pub witness: Vec<Output<D>>,
+}
+
+/// A proof of an arbitrary set of elements against one root. See
+/// [`Prover::prove_many`].
+#[derive(Clone, Debug)]
+pub struct SparseProof<D: Digest, I = U256> {
+    /// The runs of adjacent indices proven, as `(lo, hi)` pairs in
+    /// descending order
+    pub runs: Vec<(I, I)>,
+    /// The range proof of each run in turn, each against the root below
+    /// the run before it
+    pub witness: Vec<Output<D>>,
+}
+
+/// The elements a [`RangeProof`] or [`SparseProof`] proves, with their
+/// indices
+pub type ProvenElements<P> = Vec<(<P as Accumulator>::Index, Element<P>)>;
+
+/// A witness as a path of nodes tagged with their [`Direction`]
+pub type Path<P> = Vec<(Element<P>, Direction)>;
+
+/// An element at an index with the witness proving it, as verified in
+/// bulk by [`Prover::verify_shared_root`]
+pub type WitnessItem<P> = (<P as Accumulator>::Index, Element<P>, Vec<Element<P>>);
+
+/// Everything needed to verify one membership proof, without access to the
+/// prover: the root, the indices, the element, and the witness
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofBundle<D: Digest, I = U256> {
+    /// The root the proof is against
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub root: Output<D>,
+    /// The root at index 0, from [`Accumulator::genesis`]
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub genesis: Output<D>,
+    /// The index of `root`
+    pub i: I,
+    /// The index of `element`
+    pub j: I,
+    /// The element being proven
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub element: Output<D>,
+    /// The witness proving `element` against `root`
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex::vec"))]
+    pub witness: Vec<Output<D>>,
+}
+
+impl<D: Digest, I: Index> ProofBundle<D, I> {
+    /// Check the proof. This is equivalent to [`Prover::verify_with_genesis`].
+    pub fn verify(&self) -> Result<(), ProverError> {
+        let triple = verify::walk::<D, I>(
+            &self.root,
+            &self.genesis,
+            self.i,
+            self.j,
+            &self.witness,
+            |_| {},
+        )?;
+        verify::check_element::<D>(triple, &self.element)
+    }
+}
+
+/// What a third party needs to verify future proofs against one fixed root,
+/// without the prover: the root, the size it is the root of, and the name of
+/// the digest. The size is pinned, so a proof against any other size is
+/// rejected.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerifierPackage<D: Digest, I = U256> {
+    /// The exported root
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub root: Output<D>,
+    /// The number of elements `root` commits to, and so its index
+    pub size: I,
+    /// The root at index 0, from [`Accumulator::genesis`]
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub genesis: Output<D>,
+    /// The name of the digest, from [`NamedDigest::NAME`]. `verify` fails
+    /// with `DigestMismatch` if it is not the name of `D`.
+    pub digest: String,
+}
+
+impl<D: NamedDigest, I: Index> VerifierPackage<D, I> {
+    /// Check that `witness` proves `element` at `j` against the exported
+    /// root. This is [`Prover::verify`] with `i` fixed to `size`, once
+    /// the package is known to be for the digest `D`.
+    pub fn verify(
+        &self,
+        witness: &[Output<D>],
+        j: I,
+        element: &Output<D>,
+    ) -> Result<(), ProverError> {
+        if self.digest != D::NAME {
+            return Err(ProverError::DigestMismatch);
+        }
+        let triple =
+            verify::walk::<D, I>(&self.root, &self.genesis, self.size, j, witness, |_| {})?;
+        verify::check_element::<D>(triple, element)
+    }
+}
+
+/// What a follower at size `from` needs to catch up with a leader at size
+/// `to`: the elements it is missing, and a witness that the leader's root
+/// extends the follower's. See [`Prover::diff_since`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncDelta<D: Digest, I = U256> {
+    /// The follower's size
+    pub from: I,
+    /// The leader's size
+    pub to: I,
+    /// The leader's root at `to`
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex"))]
+    pub root: Output<D>,
+    /// The elements at `from + 1..=to`, in order
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex::vec"))]
+    pub elements: Vec<Output<D>>,
+    /// The witness from [`Prover::prove_consistency_minimal`], or empty if
+    /// `from` is 0
+    #[cfg_attr(feature = "serialize", serde(with = "crate::serde_hex::vec"))]
+    pub witness: Vec<Output<D>>,
+}
+
+impl<D: Digest, I: Index> SyncDelta<D, I> {
+    /// Check the delta against `follower` and catch it up to `to`: the
+    /// follower must be at `from`, `elements` must cover `from + 1..=to`,
+    /// the witness must show that `root` extends the follower's root, and
+    /// inserting `elements` must arrive at `root`. The witness is checked
+    /// before anything is inserted, so a delta from another fork is turned
+    /// away without touching the follower. The elements are then inserted
+    /// in a [`Txn`], which is only committed if they arrive at `root`, and
+    /// the new root is returned.
+    ///
+    /// Fails with `InconsistentState` if the sizes or the replayed root
+    /// don't match, and as [`Prover::verify_consistency`] does if the
+    /// witness doesn't.
+    pub fn apply(&self, follower: &mut SimpleProver<D, I>) -> Result<Output<D>, ProverError>
+    where
+        D: Clone + Default,
+    {
+        if follower.len() != self.from || self.from > self.to {
+            return Err(ProverError::InconsistentState("follower is not at from"));
+        }
+        if U256::from(self.elements.len()) != (self.to - self.from).into() {
+            return Err(ProverError::InconsistentState(
+                "elements don't cover from..=to",
+            ));
+        }
+        // Any log extends the empty one, so there is no witness from 0
+        if self.from.is_zero() {
+            if !self.witness.is_empty() {
+                return Err(ProverError::WitnessTooLong);
+            }
+        } else if verify::walk_to_root::<D, I>(
+            &self.root,
+            &follower.genesis(),
+            self.to,
+            self.from,
+            &self.witness,
+        )? != &follower.get_root()
+        {
+            return Err(ProverError::RiMismatch(self.witness.len() / 3));
+        }
+
+        let mut txn = follower.begin();
+        for element in &self.elements {
+            txn.insert(element);
+        }
+        let root = txn.get_root();
+        if root != self.root {
+            return Err(ProverError::InconsistentState(
+                "elements don't lead to root",
+            ));
+        }
+        txn.commit();
+        Ok(root)
+    }
+}
+
+/// Where two provers' histories first differ, with each prover's proof of
+/// its own element there against its own latest root
+#[derive(Clone, Debug)]
+pub struct Divergence<D: Digest, I = U256> {
+    /// The first index at which the roots differ
+    pub index: I,
+    /// `prove(index)` from the first prover
+    pub ours: Vec<Output<D>>,
+    /// `prove(index)` from the second prover
+    pub theirs: Vec<Output<D>>,
+}
+
+/// The [`Divergence`] type for a given prover
+pub type DivergenceOf<P> = Divergence<<P as Accumulator>::Digest, <P as Accumulator>::Index>;
+
+/// Errors from proving and verifying. More variants may be added in minor
+/// releases, so matches must include a wildcard arm.
 #[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub enum ProverError {
     MissingHistory(U256),
     OutOfBounds,
     WitnessTooShort,
-    RiMismatch,
+    /// The witness has nodes beyond those the proof needs
+    WitnessTooLong,
+    /// The triple at this position in the witness (counting triples, not
+    /// nodes) does not hash to the root expected at that step
+    RiMismatch(usize),
     XiMismatch,
+    /// A byte slice of this length was given where an element was expected
+    InvalidElementLength(usize),
+    /// The element was never inserted, or its history has been pruned
+    ElementNotFound,
+    /// Prover parts don't agree with each other
+    InconsistentState(&'static str),
+    /// An encoded witness could not be parsed, or its indices don't match
+    /// its length
+    MalformedWitness,
+    /// The genesis, the empty root unless the log was started from another,
+    /// was expected at a non-zero index. Only the sentinel at index 0 may
+    /// hold it.
+    UnexpectedEmptyRoot,
+    /// A proof of this many nodes is over the configured maximum
+    ProofTooLarge(usize),
+    /// No retained root after the sentinel has the given value
+    UnknownRoot,
+    /// Data recorded as produced by one digest was read as another's
+    DigestMismatch,
 }
 
+/// An append-only accumulator.
+///
+/// This trait is not object safe: it requires `Default + Clone`, and several
+/// methods are generic (`impl Into<Self::Index>`, `impl AsRef<[u8]>`). Use
+/// [`DynAccumulator`] to work with accumulators behind `dyn`.
 pub trait Accumulator: Default + Clone {
-    type Digest: Digest<OutputSize = TU32>;
+    type Digest: Digest;
+    type Index: Index;
+
+    /// The size in bytes of the digest's outputs, and so of every element,
+    /// root and witness node
+    const OUTPUT_SIZE: usize = <<Self::Digest as Digest>::OutputSize as Unsigned>::USIZE;
 
     fn get_digest() -> Self::Digest {
         Self::Digest::new()
     }
 
+    /// The root of an empty accumulator. This is also the sentinel value
+    /// stored at index 0.
+    fn empty_root() -> Element<Self> {
+        Default::default()
+    }
+
+    /// The digest's [`NamedDigest::NAME`], to record with serialized roots
+    /// and proofs so that a reader can pick the matching type
+    fn digest_name() -> &'static str
+    where
+        Self::Digest: NamedDigest,
+    {
+        <Self::Digest as NamedDigest>::NAME
+    }
+
+    /// The root at index 0. This is `empty_root()` unless the accumulator
+    /// was started from another genesis, and verifiers reject it at any
+    /// later index.
+    fn genesis(&self) -> Element<Self> {
+        self.get_state(Self::Index::zero())
+            .unwrap_or_else(Self::empty_root)
+    }
+
+    /// True if `element` is the empty root (the index 0 sentinel)
+    fn is_empty_root(element: &Element<Self>) -> bool {
+        *element == Self::empty_root()
+    }
+
     fn from_elements(elements: impl Iterator<Item = Element<Self>>) -> Self
     where
         Self: Sized,
@@ -34,45 +371,101 @@ pub trait Accumulator: Default + Clone {
         acc
     }
 
-    #[doc(hidden)]
-    fn state_mut(&mut self) -> &mut BTreeMap<usize, Element<Self>>;
+    /// [`Accumulator::from_elements`], also returning the number of elements
+    /// inserted and the final root
+    fn from_elements_reporting(
+        elements: impl Iterator<Item = Element<Self>>,
+    ) -> (Self, Self::Index, Element<Self>)
+    where
+        Self: Sized,
+    {
+        let acc = Self::from_elements(elements);
+        let (len, root) = (acc.len(), acc.get_root());
+        (acc, len, root)
+    }
 
     #[doc(hidden)]
     fn state(&self) -> &BTreeMap<usize, Element<Self>>;
 
     /// Get the
-    fn get_state(&self, i: impl Into<U256>) -> Option<Element<Self>> {
-        let i: U256 = i.into();
+    fn get_state(&self, i: impl Into<Self::Index>) -> Option<Element<Self>> {
+        let i: Self::Index = i.into();
         if i.is_zero() {
-            Some(Default::default())
+            Some(Self::empty_root())
         } else {
-            self.state().get(&(i.trailing_zeros() as usize)).copied()
+            self.state().get(&(i.trailing_zeros() as usize)).cloned()
         }
     }
 
-    fn set_state(&mut self, i: impl Into<U256>, element: &Element<Self>) {
-        self.state_mut().insert(i.into().trailing_zeros() as usize, *element);
-    }
-
+    /// Returns the number of populated state slots. This is roughly
+    /// `log2(len())`, NOT the number of inserted elements. It is always
+    /// `<= len()`.
     fn state_len(&self) -> usize {
         self.state().len()
     }
 
     /// Returns the number of elements that have been inserted into the
-    /// accumulator
-    fn len(&self) -> U256;
+    /// accumulator (`k`)
+    fn len(&self) -> Self::Index;
 
     fn is_empty(&self) -> bool {
         self.len().is_zero()
     }
 
-    /// Returns the latest state root, or the 0 element if empty
+    /// Returns the latest state root. This is always defined: an empty
+    /// accumulator has the empty root, `Self::empty_root()`.
     fn get_root(&self) -> Element<Self> {
-        self.get_state(self.len()).unwrap_or_else(Default::default)
+        self.get_state(self.len()).unwrap_or_else(Self::empty_root)
+    }
+
+    /// Returns the latest state root as bytes
+    fn root_bytes(&self) -> Vec<u8> {
+        self.get_root().to_vec()
     }
 
     fn insert(&mut self, element: &Element<Self>) -> Element<Self>;
 
+    /// Insert an already-hashed element given as bytes. Unlike
+    /// `insert_data`, the bytes are not hashed, so they must be exactly the
+    /// digest's output size.
+    fn insert_bytes(&mut self, bytes: &[u8]) -> Result<Element<Self>, ProverError> {
+        if bytes.len() != Self::OUTPUT_SIZE {
+            return Err(ProverError::InvalidElementLength(bytes.len()));
+        }
+        Ok(self.insert(&Element::<Self>::clone_from_slice(bytes)))
+    }
+
+    /// Insert a digest-sized value produced outside this accumulator, such
+    /// as an element from an accumulator over a different digest, as a leaf.
+    /// This is the same as `insert_bytes`.
+    ///
+    /// When migrating from digest `D1` to `D2`, the old `D1` elements can be
+    /// carried over as they are with this method, keeping their values and
+    /// their proofs' leaves. Alternatively, re-hash each old element's bytes
+    /// under `D2` with `insert_data`, so that every leaf is a `D2` output;
+    /// verifiers must then know which form was used.
+    fn insert_foreign_element(&mut self, bytes: &[u8]) -> Result<Element<Self>, ProverError> {
+        self.insert_bytes(bytes)
+    }
+
+    /// Insert `other`'s latest root as a leaf, for a log of logs. To prove
+    /// an element of `other` to someone holding only this log's root:
+    ///
+    /// 1. prove the element at `j` against the sub-log's root at its length
+    ///    `n`, with `other.prove_from(n, j)`
+    /// 2. prove that root, as the leaf at its index `m` here, against this
+    ///    log's root, with `self.prove(m)`
+    ///
+    /// The verifier checks the second proof with the sub-log's root as the
+    /// element, then the first against that root. Later inserts into `other`
+    /// are not reflected here until its root is inserted again.
+    fn insert_root_of<A: Accumulator<Digest = Self::Digest>>(
+        &mut self,
+        other: &A,
+    ) -> Element<Self> {
+        self.insert(&other.get_root())
+    }
+
     fn insert_iter(&mut self, elements: impl Iterator<Item = Element<Self>>) -> Element<Self> {
         let mut result = Default::default();
         for e in elements {
@@ -84,36 +477,944 @@ pub trait Accumulator: Default + Clone {
     fn insert_data(&mut self, data: impl AsRef<[u8]>) -> Element<Self> {
         self.insert(&Self::Digest::digest(data.as_ref()))
     }
+
+    /// Hash `data` and insert it. This is `insert_data` without the generic
+    /// argument.
+    fn ingest(&mut self, data: &[u8]) -> Element<Self> {
+        self.insert_data(data)
+    }
+
+    /// Hash an owned buffer and insert it. The buffer is dropped before the
+    /// insert.
+    fn insert_owned(&mut self, data: Vec<u8>) -> Element<Self> {
+        let element = Self::Digest::digest(&data);
+        drop(data);
+        self.insert(&element)
+    }
+
+    /// Hash and insert each owned buffer in turn, dropping each as soon as it
+    /// has been hashed. Returns the last root.
+    fn insert_owned_many(&mut self, data: impl IntoIterator<Item = Vec<u8>>) -> Element<Self> {
+        let mut result = self.get_root();
+        for d in data {
+            result = self.insert_owned(d);
+        }
+        result
+    }
+
+    /// Hash several chunks as a single element, and insert it. The element
+    /// is identical to `insert_data` of the concatenated chunks.
+    fn insert_chunks(
+        &mut self,
+        chunks: impl IntoIterator<Item = impl AsRef<[u8]>>,
+    ) -> Element<Self> {
+        let mut d = Self::Digest::new();
+        for chunk in chunks {
+            d.update(chunk);
+        }
+        self.insert(&d.finalize())
+    }
+}
+
+/// The object-safe subset of [`Accumulator`], working in bytes. This is
+/// implemented for every accumulator.
+pub trait DynAccumulator {
+    /// Returns the latest state root as bytes
+    fn dyn_root_bytes(&self) -> Vec<u8>;
+
+    /// Hash `data` and insert it, returning the new root as bytes
+    fn dyn_ingest(&mut self, data: &[u8]) -> Vec<u8>;
+}
+
+impl<A: Accumulator> DynAccumulator for A {
+    fn dyn_root_bytes(&self) -> Vec<u8> {
+        Accumulator::root_bytes(self)
+    }
+
+    fn dyn_ingest(&mut self, data: &[u8]) -> Vec<u8> {
+        Accumulator::ingest(self, data).to_vec()
+    }
 }
 
 pub trait Prover: Accumulator {
-    fn elements(&self) -> &BTreeMap<U256, Element<Self>>;
+    /// The map type holding the prover's elements and roots
+    type History: HistoryStore<Self::Index, Element<Self>>;
 
-    fn get_element(&self, i: &U256) -> Option<&Element<Self>> {
-        self.elements().get(&i)
+    /// All inserted elements by index. This includes the sentinel at index
+    /// 0, so it has `len() + 1` entries. Use `iter_elements` to skip it.
+    fn elements(&self) -> &Self::History;
+
+    fn get_element(&self, i: &Self::Index) -> Option<&Element<Self>> {
+        self.elements().get(i)
     }
 
-    fn r(&self) -> &BTreeMap<U256, Element<Self>>;
+    /// Iterate over the inserted elements in index order, excluding the
+    /// sentinel
+    fn iter_elements(&self) -> Entries<'_, Self::Index, Element<Self>> {
+        Box::new(self.elements().entries().filter(|(i, _)| !i.is_zero()))
+    }
 
-    fn get_r(&self, i: &U256) -> Option<&Element<Self>> {
-        self.r().get(&i)
+    /// All roots by index, including the empty root at index 0
+    fn r(&self) -> &Self::History;
+
+    fn get_r(&self, i: &Self::Index) -> Option<&Element<Self>> {
+        self.r().get(i)
+    }
+
+    /// A copy of the `r` map with equal values stored once. See
+    /// [`Interned`]: roots don't repeat in practice, so the map that shrinks
+    /// is usually `Interned::from_store(self.elements())`, for logs of
+    /// repeated elements.
+    fn compact_r_map(&self) -> Interned<Self::Index, Element<Self>> {
+        Interned::from_store(self.r())
     }
 
+    /// Hash-chain every root, `r[1]` through `r[k]`, into a single commitment
+    /// to the accumulator's whole history: starting from all zeros,
+    /// `h = H(h || i || r[i])`, with `i` as a 32-byte big-endian integer.
+    /// This is not the accumulator root, and is not related to it by any
+    /// proof. Fails with `MissingHistory` at the first root that has been
+    /// pruned, rather than committing to a partial history.
+    fn fold_roots(&self) -> Result<Element<Self>, ProverError> {
+        let mut h = Element::<Self>::default();
+        let mut i = Self::Index::zero();
+        while i < self.len() {
+            i = i + Self::Index::one();
+            let r_i = self
+                .get_r(&i)
+                .ok_or_else(|| ProverError::MissingHistory(i.into()))?;
+            let mut buf = [0u8; 32];
+            i.into().to_big_endian(&mut buf);
+            let mut d = Self::get_digest();
+            d.update(&h);
+            d.update(buf);
+            d.update(r_i);
+            h = d.finalize();
+        }
+        Ok(h)
+    }
+
+    /// The index of the first insert of `element`, if any. This is a linear
+    /// scan.
+    fn index_of(&self, element: &Element<Self>) -> Option<Self::Index> {
+        self.iter_elements()
+            .find(|(_, e)| *e == element)
+            .map(|(i, _)| i)
+    }
+
+    /// The root after the `i`th insert, or `None` if it is not known
+    fn root_at(&self, i: impl Into<Self::Index>) -> Option<Element<Self>> {
+        self.get_r(&i.into()).cloned()
+    }
+
+    /// The latest retained root at or before `i`, with its index. After
+    /// pruning this is the nearest checkpoint a proof can still be anchored
+    /// to. Only `None` if the sentinel at 0 has been removed too.
+    fn root_at_or_before(&self, i: impl Into<Self::Index>) -> Option<(Self::Index, Element<Self>)> {
+        let i = i.into();
+        self.r()
+            .last_at_or_before(&i)
+            .map(|(k, r_k)| (k, r_k.clone()))
+    }
+
+    /// The root `r_i` skips back to, i.e. `r[pred(i)]`.
+    ///
+    /// `pred(i)` clears the lowest set bit of `i`, so `pred(i)..i` is the
+    /// aligned block of `2^trailing_zeros(i)` inserts ending at `i`. Every
+    /// root commits to both its predecessor and the root before that block,
+    /// which is what lets a proof skip the whole block in one step. Returns
+    /// `None` for the sentinel at index 0, which has no block.
+    fn subtree_root(&self, i: impl Into<Self::Index>) -> Option<Element<Self>> {
+        let i = i.into();
+        if i.is_zero() {
+            return None;
+        }
+        self.root_at(utils::pred(i))
+    }
+
+    /// Prove the element at `j` against the root at `i`. Fails with
+    /// `OutOfBounds` if `j > i` or `j == 0`: the sentinel at index 0 is not
+    /// an inserted element, so there is nothing to prove there.
     fn prove_from(
         &self,
-        i: impl Into<U256>,
-        j: impl Into<U256>,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
     ) -> Result<Vec<Element<Self>>, ProverError>;
 
-    fn prove(&self, j: impl Into<U256>) -> Result<Vec<Element<Self>>, ProverError> {
-        self.prove_from(self.state_len(), j)
+    /// Iterate lazily over the nodes of the witness proving `j` against `i`,
+    /// without collecting them into a `Vec`
+    fn proof_walk(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+    ) -> ProofWalk<'_, Self> {
+        ProofWalk::new(self, i.into(), j.into())
+    }
+
+    /// The index behind each node of the witness proving `j` against `i`,
+    /// in witness order: for each triple, the element's index `k`, then the
+    /// indices of the roots `k - 1` and `pred(k)`. This is [`ProofWalk`]
+    /// over indices rather than values, so that a light client can fetch
+    /// everything a proof depends on in one request. Only the indices are
+    /// used, so this doesn't fail on pruned history.
+    fn proof_chain_indices(
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+    ) -> Result<Vec<Self::Index>, ProverError> {
+        let (i, j) = (i.into(), j.into());
+        if j > i || j.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        let mut indices = Vec::with_capacity(3 * utils::proof_steps(i, j));
+        let mut k = i;
+        loop {
+            indices.extend_from_slice(&[k, k - Self::Index::one(), utils::pred(k)]);
+            if k == j {
+                return Ok(indices);
+            }
+            k = utils::step(k, j).1;
+        }
+    }
+
+    /// The smallest `j` that can still be proven against the root at `i`
+    /// from the retained history, or `None` if there is none. Roots never
+    /// change once written, but pruning removes the nodes that proofs need,
+    /// and the provable indices need not be contiguous. This tries each
+    /// retained element in order, so it is slow on large histories.
+    fn min_provable_index(&self, i: impl Into<Self::Index>) -> Option<Self::Index> {
+        let i = i.into();
+        self.iter_elements()
+            .map(|(j, _)| j)
+            .take_while(|j| *j <= i)
+            .find(|j| self.proof_walk(i, *j).all(|node| node.is_ok()))
+    }
+
+    /// Prove `j` against `i`, returning the element at `j` along with the
+    /// witness, so that the pair passed to `verify` always matches. The
+    /// element is taken from the witness's last triple.
+    fn prove_with_element(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+    ) -> Result<(Element<Self>, Vec<Element<Self>>), ProverError> {
+        let witness = self.prove_from(i, j)?;
+        Ok((witness[witness.len() - 3].clone(), witness))
+    }
+
+    /// Append the witness proving `j` against `i` to `buf`, so that one
+    /// allocation can be reused across many proofs. On error `buf` is left
+    /// as it was.
+    fn prove_into(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+        buf: &mut Vec<Element<Self>>,
+    ) -> Result<(), ProverError> {
+        buf.extend(self.prove_from(i, j)?);
+        Ok(())
+    }
+
+    /// Prove every element in `j_lo..=j_hi` against the root at `i`. This
+    /// costs one triple per element in the range on top of the proof of
+    /// `j_hi`, rather than a full proof for each.
+    fn prove_slice(
+        &self,
+        i: impl Into<Self::Index>,
+        j_lo: impl Into<Self::Index>,
+        j_hi: impl Into<Self::Index>,
+    ) -> Result<RangeProof<Self::Digest, Self::Index>, ProverError> {
+        let (lo, hi) = (j_lo.into(), j_hi.into());
+        if lo > hi || lo.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        let mut witness = self.prove_from(i, hi)?;
+        let mut k = hi;
+        while k > lo {
+            k = k - Self::Index::one();
+            self.prove_into(k, k, &mut witness)?;
+        }
+        Ok(RangeProof { lo, hi, witness })
+    }
+
+    /// Verify a [`RangeProof`] against `r_i`, returning the proven elements
+    /// in index order
+    fn verify_slice(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        proof: &RangeProof<Self::Digest, Self::Index>,
+    ) -> Result<ProvenElements<Self>, ProverError> {
+        let mut proven = vec![];
+        let (end, _) = verify::walk_slice::<Self::Digest, _>(
+            r_i,
+            &Self::empty_root(),
+            i,
+            proof.lo,
+            proof.hi,
+            &proof.witness,
+            0,
+            &mut proven,
+        )?;
+        if end != proof.witness.len() {
+            return Err(ProverError::WitnessTooLong);
+        }
+        proven.reverse();
+        Ok(proven)
+    }
+
+    /// Prove every element in `js` against the root at `i`, in any order and
+    /// with repeats allowed. Adjacent indices are grouped into runs, each
+    /// proven as with `prove_slice`. Each run after the first is proven
+    /// against the root just below the previous run, which that run's last
+    /// triple commits to, so the chain down from `i` is walked only once.
+    fn prove_many(
+        &self,
+        i: impl Into<Self::Index>,
+        js: &[Self::Index],
+    ) -> Result<SparseProof<Self::Digest, Self::Index>, ProverError> {
+        let mut js = js.to_vec();
+        js.sort_unstable_by(|a, b| b.cmp(a));
+        js.dedup();
+
+        let mut runs: Vec<(Self::Index, Self::Index)> = vec![];
+        for j in js {
+            match runs.last_mut() {
+                Some((lo, _)) if j + Self::Index::one() == *lo => *lo = j,
+                _ => runs.push((j, j)),
+            }
+        }
+
+        let mut witness = vec![];
+        let mut at = i.into();
+        for &(lo, hi) in &runs {
+            witness.extend(self.prove_slice(at, lo, hi)?.witness);
+            at = lo - Self::Index::one();
+        }
+        Ok(SparseProof { runs, witness })
+    }
+
+    /// Verify a [`SparseProof`] against `r_i`, returning the proven elements
+    /// in index order
+    fn verify_sparse(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        proof: &SparseProof<Self::Digest, Self::Index>,
+    ) -> Result<ProvenElements<Self>, ProverError> {
+        let mut proven = vec![];
+        let (mut anchor, mut at, mut offset) = (r_i.clone(), i, 0);
+        for &(lo, hi) in &proof.runs {
+            let (end, prev) = verify::walk_slice::<Self::Digest, _>(
+                &anchor,
+                &Self::empty_root(),
+                at,
+                lo,
+                hi,
+                &proof.witness,
+                offset,
+                &mut proven,
+            )?;
+            anchor = prev;
+            at = lo - Self::Index::one();
+            offset = end;
+        }
+        if offset != proof.witness.len() {
+            return Err(ProverError::WitnessTooLong);
+        }
+        proven.reverse();
+        Ok(proven)
+    }
+
+    /// Hash `data`, find where it was inserted, and prove it against `i`.
+    /// Returns the index found along with the witness.
+    fn witness_for_data(
+        &self,
+        i: impl Into<Self::Index>,
+        data: impl AsRef<[u8]>,
+    ) -> Result<(Self::Index, Vec<Element<Self>>), ProverError> {
+        let element = Self::Digest::digest(data.as_ref());
+        let j = self
+            .index_of(&element)
+            .ok_or(ProverError::ElementNotFound)?;
+        Ok((j, self.prove_from(i, j)?))
+    }
+
+    /// The first index at which the roots of `self` and `other` differ, if
+    /// any within their shared length. As equal roots imply equal prefixes,
+    /// this is a binary search over `root_at`. A missing root counts as a
+    /// difference, so pruned history may give a misleading answer.
+    fn first_divergence(&self, other: &Self) -> Option<Self::Index> {
+        let limit = std::cmp::min(self.len(), other.len());
+        let agree = |n: Self::Index| match (self.root_at(n), other.root_at(n)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        };
+
+        let mut powers = vec![];
+        let mut p = Self::Index::one();
+        while p <= limit {
+            powers.push(p);
+            p = p + p;
+        }
+        // The largest `n <= limit` the roots agree at
+        let mut n = Self::Index::zero();
+        for p in powers.into_iter().rev() {
+            if n + p <= limit && agree(n + p) {
+                n = n + p;
+            }
+        }
+
+        if n == limit {
+            None
+        } else {
+            Some(n + Self::Index::one())
+        }
+    }
+
+    /// Find the first divergence between `self` and `other`, and prove each
+    /// side's element there against its own latest root
+    fn prove_divergence(&self, other: &Self) -> Result<Option<DivergenceOf<Self>>, ProverError> {
+        let index = match self.first_divergence(other) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        Ok(Some(Divergence {
+            index,
+            ours: self.prove(index)?,
+            theirs: other.prove(index)?,
+        }))
+    }
+
+    /// Prove the element at `j` against the root with value `root`, for a
+    /// verifier that knows the root but not the length it was taken at.
+    /// Returns that length, `i`, along with the witness. This is a linear
+    /// scan over the borrowed roots, so it doesn't copy the history.
+    ///
+    /// Fails with `UnknownRoot` if no root after the sentinel has that value,
+    /// for instance because it was pruned. This is not `MissingHistory`, as
+    /// that names the missing index, and here the index is what's unknown.
+    fn prove_against_root(
+        &self,
+        root: &Element<Self>,
+        j: impl Into<Self::Index>,
+    ) -> Result<(Self::Index, Vec<Element<Self>>), ProverError> {
+        let j = j.into();
+        let i = self
+            .r()
+            .entries()
+            .find(|(i, r)| *r == root && !i.is_zero())
+            .map(|(i, _)| i)
+            .ok_or(ProverError::UnknownRoot)?;
+        Ok((i, self.prove_from(i, j)?))
+    }
+
+    /// [`Prover::prove_against_root`] without the resolved length, for a
+    /// verifier that already has it from wherever it got the root
+    fn prove_from_root(
+        &self,
+        root: &Element<Self>,
+        j: impl Into<Self::Index>,
+    ) -> Result<Vec<Element<Self>>, ProverError> {
+        Ok(self.prove_against_root(root, j)?.1)
+    }
+
+    /// Prove that the log at size `b` extends the log at size `a`, for a
+    /// verifier holding both published roots. The witness is the chain of
+    /// triples from `b` down to `a`, without the triple at `a` itself: the
+    /// last triple already commits to `r_a`, so this is one triple shorter
+    /// than `prove_from(b, a)`, and empty if `a == b`. Check it with
+    /// [`Prover::verify_consistency`]. Fails with `OutOfBounds` if `a > b`
+    /// or `a == 0`.
+    fn prove_consistency_minimal(
+        &self,
+        a: impl Into<Self::Index>,
+        b: impl Into<Self::Index>,
+    ) -> Result<Vec<Element<Self>>, ProverError> {
+        let (a, b) = (a.into(), b.into());
+        if a.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        let mut witness = self.prove_from(b, a)?;
+        witness.truncate(witness.len() - 3);
+        Ok(witness)
+    }
+
+    /// The elements a follower at size `their_len` is missing, with a
+    /// witness that the current root extends theirs, so that the follower
+    /// can verify and catch up in one round. Fails with `OutOfBounds` if
+    /// `their_len` is beyond `len()`, and `MissingHistory` if any of the
+    /// history needed has been pruned.
+    fn diff_since(
+        &self,
+        their_len: impl Into<Self::Index>,
+    ) -> Result<SyncDelta<Self::Digest, Self::Index>, ProverError> {
+        let (from, to) = (their_len.into(), self.len());
+        if from > to {
+            return Err(ProverError::OutOfBounds);
+        }
+        let witness = if from.is_zero() {
+            vec![]
+        } else {
+            self.prove_consistency_minimal(from, to)?
+        };
+        let mut elements = vec![];
+        let mut k = from;
+        while k < to {
+            k = k + Self::Index::one();
+            let x = self
+                .get_element(&k)
+                .ok_or_else(|| ProverError::MissingHistory(k.into()))?;
+            elements.push(x.clone());
+        }
+        Ok(SyncDelta {
+            from,
+            to,
+            root: self.get_root(),
+            elements,
+            witness,
+        })
+    }
+
+    /// Prove `j` against `i` as a Merkle-style path: the nodes of the
+    /// witness in order, each tagged with its role. Per triple, the element
+    /// comes first, then the `prev` and `pred` roots, one of which is the
+    /// `Next` step of the walk except in the last triple.
+    fn proof_as_path(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+    ) -> Result<Path<Self>, ProverError> {
+        let (i, j) = (i.into(), j.into());
+        let witness = self.prove_from(i, j)?;
+        Ok(path::to_path::<Self::Digest, _>(i, j, &witness))
+    }
+
+    /// Prove the element at `j` against the current root (i.e. `i = len()`)
+    fn prove(&self, j: impl Into<Self::Index>) -> Result<Vec<Element<Self>>, ProverError> {
+        debug_assert!(U256::from(self.state_len()) <= self.len().into());
+        self.prove_from(self.len(), j)
+    }
+
+    /// Prove the latest element against the latest root, packaged with
+    /// everything a verifier needs
+    fn latest_proof_bundle(&self) -> Result<ProofBundle<Self::Digest, Self::Index>, ProverError> {
+        let i = self.len();
+        let element = self
+            .get_element(&i)
+            .ok_or(ProverError::OutOfBounds)?
+            .clone();
+        Ok(ProofBundle {
+            root: self.get_root(),
+            genesis: self.genesis(),
+            i,
+            j: i,
+            element,
+            witness: self.prove_from(i, i)?,
+        })
     }
 
+    /// Export the current root and size as a [`VerifierPackage`], for a
+    /// third party to verify later proofs against this root
+    fn export_for_verifier(&self) -> VerifierPackage<Self::Digest, Self::Index>
+    where
+        Self::Digest: NamedDigest,
+    {
+        VerifierPackage {
+            root: self.get_root(),
+            size: self.len(),
+            genesis: self.genesis(),
+            digest: Self::digest_name().to_string(),
+        }
+    }
+
+    /// Prove `j` against the checkpoint in `checkpoints` that gives the
+    /// shortest proof. Checkpoints outside `j..=i` are ignored, and if none
+    /// remain the proof is anchored at `i` itself.
+    fn prove_anchored(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+        checkpoints: &[Self::Index],
+    ) -> Result<AnchoredProof<Self::Digest, Self::Index>, ProverError> {
+        let (i, j) = (i.into(), j.into());
+        let checkpoint = checkpoints
+            .iter()
+            .copied()
+            .filter(|c| j <= *c && *c <= i)
+            .min_by_key(|c| utils::proof_steps(*c, j))
+            .unwrap_or(i);
+        Ok(AnchoredProof {
+            checkpoint,
+            witness: self.prove_from(checkpoint, j)?,
+        })
+    }
+
+    /// Verify an anchored proof. `checkpoint_root` is trusted as the root at
+    /// `proof.checkpoint`; it is the caller's job to have checked it against
+    /// the published value.
+    fn verify_anchored(
+        checkpoint_root: &Element<Self>,
+        j: Self::Index,
+        proof: &AnchoredProof<Self::Digest, Self::Index>,
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        Self::verify(
+            checkpoint_root,
+            proof.checkpoint,
+            j,
+            &proof.witness,
+            element,
+        )
+    }
+
+    /// [`Prover::verify`] from a checkpoint root `trusted_r_m` at `m`,
+    /// which is taken as given: the result is only as good as its source.
+    fn verify_from_checkpoint(
+        trusted_r_m: &Element<Self>,
+        m: Self::Index,
+        j: Self::Index,
+        witness_suffix: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        Self::verify(trusted_r_m, m, j, witness_suffix, element)
+    }
+
+    /// The number of hash operations needed to verify a proof of `j`
+    /// against `i`. This is the number of `(x, prev, pred)` triples in the
+    /// witness, as `verify` performs exactly one digest per triple. Proving
+    /// performs no hashing, only lookups.
+    fn proof_hash_count(i: impl Into<Self::Index>, j: impl Into<Self::Index>) -> usize {
+        utils::proof_steps(i.into(), j.into())
+    }
+
+    /// The number of inserts covered by the skip from `i` to `pred(i)`,
+    /// i.e. `2^trailing_zeros(i)`.
+    ///
+    /// Together with [`Prover::block_start`] this splits history into the
+    /// skip-list segments: the root at `i` commits to `r[block_start(i)]`,
+    /// so a proof can jump over the `block_size(i)` inserts in
+    /// `block_start(i) + 1..=i` in one step. Blocks nest: each is either
+    /// contained in or disjoint from every other. Both are 0 for the
+    /// sentinel at index 0.
+    fn block_size(i: impl Into<Self::Index>) -> Self::Index {
+        let i = i.into();
+        if i.is_zero() {
+            return i;
+        }
+        utils::highest_divisor_power_of_2(i)
+    }
+
+    /// The index the root at `i` skips back to, `pred(i)`. See
+    /// [`Prover::block_size`].
+    fn block_start(i: impl Into<Self::Index>) -> Self::Index {
+        let i = i.into();
+        if i.is_zero() {
+            return i;
+        }
+        utils::pred(i)
+    }
+
+    /// The branches `prove_from(i, j)` takes, in order. Empty if `j >= i`.
+    fn proof_decisions(i: impl Into<Self::Index>, j: impl Into<Self::Index>) -> Vec<Branch> {
+        utils::branches(i.into(), j.into())
+    }
+
+    /// Verify that `witness` proves `element` at `j` against `r_i`, the
+    /// root at `i`. The witness must be exactly the nodes the proof needs:
+    /// trailing nodes are rejected with `WitnessTooLong`. The root at index
+    /// 0 is taken to be `empty_root()`; see `verify_with_genesis` for logs
+    /// started from another genesis.
     fn verify(
         r_i: &Element<Self>,
-        i: U256,
-        j: U256,
+        i: Self::Index,
+        j: Self::Index,
         witness: &[Element<Self>],
         element: &Element<Self>,
     ) -> Result<(), ProverError>;
+
+    /// [`Prover::verify`] for a log whose root at index 0 is `genesis`.
+    /// Every root the witness leads through is at an index of at least 1,
+    /// so one equal to `genesis` fails with `UnexpectedEmptyRoot`.
+    fn verify_with_genesis(
+        genesis: &Element<Self>,
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let triple = verify::walk::<Self::Digest, _>(r_i, genesis, i, j, witness, |_| {})?;
+        verify::check_element::<Self::Digest>(triple, element)
+    }
+
+    /// Verify against a root of which only the first `root_prefix.len()`
+    /// bytes are known, as stored by deployments that truncate roots to
+    /// save space.
+    ///
+    /// This is weaker than [`Prover::verify`]: a forger only has to match
+    /// `8 * root_prefix.len()` bits of the root, so a 16-byte prefix gives
+    /// at most 64 bits of collision resistance. An empty prefix accepts any
+    /// well-formed witness.
+    fn verify_truncated(
+        root_prefix: &[u8],
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let first =
+            verify::triple_at::<Self::Digest>(witness, 0).ok_or(ProverError::WitnessTooShort)?;
+        let r_i = verify::hash_triple::<Self::Digest>(first);
+        if !r_i.starts_with(root_prefix) {
+            return Err(ProverError::RiMismatch(0));
+        }
+        Self::verify(&r_i, i, j, witness, element)
+    }
+
+    /// Verify `witness` against this prover's root at `i`, and its own
+    /// genesis. Fails with `MissingHistory` if the root at `i` is not known.
+    fn verify_at(
+        &self,
+        i: impl Into<Self::Index>,
+        j: impl Into<Self::Index>,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let (i, j) = (i.into(), j.into());
+        let r_i = self
+            .root_at(i)
+            .ok_or_else(|| ProverError::MissingHistory(i.into()))?;
+        Self::verify_with_genesis(&self.genesis(), &r_i, i, j, witness, element)
+    }
+
+    /// Remove the nodes of a witness proving `j` against `i` that the
+    /// verifier can recompute: the root followed out of each triple but the
+    /// last, which is the hash of the triple after it. This shrinks the
+    /// witness by about a third.
+    ///
+    /// In exchange, [`Prover::verify_compact`] hashes each step twice, once
+    /// to rebuild the witness and once to verify it, and can't reject a bad
+    /// witness before it has all of it.
+    fn shrink_witness(
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+    ) -> Result<CompactWitness<Self::Digest>, ProverError> {
+        compact::shrink::<Self::Digest, _>(i, j, witness)
+    }
+
+    /// Rebuild a witness shrunk by [`Prover::shrink_witness`], and verify it
+    fn verify_compact(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        compact: &CompactWitness<Self::Digest>,
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let witness = compact::expand::<Self::Digest, _>(i, j, compact)?;
+        Self::verify(r_i, i, j, &witness, element)
+    }
+
+    /// Verify a path from [`Prover::proof_as_path`]. Fails with
+    /// `MalformedWitness` if the tags don't match the walk from `i` to `j`.
+    fn verify_path(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        path: &[(Element<Self>, Direction)],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let witness = path::from_path::<Self::Digest, _>(i, j, path)?;
+        Self::verify(r_i, i, j, &witness, element)
+    }
+
+    /// Verify, ignoring up to [`LENIENT_TRAILING_NODES`] nodes after those
+    /// the proof needs, for protocols that append data for future use. Only
+    /// the needed triples are read; the trailing nodes are not checked at
+    /// all. `verify` rejects any trailing nodes.
+    fn verify_lenient(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let triple = verify::walk_prefix::<Self::Digest, _>(
+            r_i,
+            &Self::empty_root(),
+            i,
+            j,
+            witness,
+            |_| {},
+        )?;
+        if witness.len() > 3 * utils::proof_steps(i, j) + LENIENT_TRAILING_NODES {
+            return Err(ProverError::WitnessTooLong);
+        }
+        verify::check_element::<Self::Digest>(triple, element)
+    }
+
+    /// Check that every triple of the witness recomputes, from `r_i` down to
+    /// `j`, without checking which element it commits to at `j`. This costs
+    /// as much as `verify`, and is useful to reject malformed witnesses
+    /// before the element is known.
+    fn verify_structure(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+    ) -> Result<(), ProverError> {
+        verify::walk::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, witness, |_| {})?;
+        Ok(())
+    }
+
+    /// `verify` on a witness stored as three columns: the `x`, `prev` and
+    /// `pred` of each triple, in witness order. The columns are walked in
+    /// lockstep without interleaving them, and the result is the same as
+    /// `verify` on the interleaved witness, except that columns of
+    /// different lengths fail with `MalformedWitness`.
+    fn verify_columns(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        x: &[Element<Self>],
+        prev: &[Element<Self>],
+        pred: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let n =
+            verify::walk_columns::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, x, prev, pred)?;
+        if x[n] != *element {
+            return Err(ProverError::XiMismatch);
+        }
+        Ok(())
+    }
+
+    /// `verify`, first rejecting a witness of more than `max_len` nodes with
+    /// `ProofTooLarge`, so that an untrusted witness can't make the verifier
+    /// hash more than `max_len / 3` triples
+    fn verify_capped(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+        max_len: usize,
+    ) -> Result<(), ProverError> {
+        if witness.len() > max_len {
+            return Err(ProverError::ProofTooLarge(witness.len()));
+        }
+        Self::verify(r_i, i, j, witness, element)
+    }
+
+    /// `verify`, returning on success the number of digests computed: one
+    /// per triple, to reconstruct the root it commits to. This is
+    /// `proof_steps(i, j)`, and lets a caller charge for verification
+    /// exactly.
+    fn verify_counted(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<usize, ProverError> {
+        let mut hashes = 1;
+        let triple =
+            verify::walk::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, witness, |_| {
+                hashes += 1
+            })?;
+        verify::check_element::<Self::Digest>(triple, element)?;
+        Ok(hashes)
+    }
+
+    /// `verify` for several `(j, element, witness)` items against the same
+    /// root, hashing each triple the witnesses share only once. Witnesses
+    /// for nearby elements share the triples at the top of the chain, so
+    /// this does less work than verifying each item on its own. Returns the
+    /// result of `verify` on each item, in order.
+    fn verify_shared_root(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        items: &[WitnessItem<Self>],
+    ) -> Vec<Result<(), ProverError>> {
+        verify::walk_shared::<Self::Digest, _>(r_i, &Self::empty_root(), i, items).0
+    }
+
+    /// `verify`, taking `u64` indices so that call sites for logs that will
+    /// never outgrow them don't need `U256`
+    fn verify_u64(
+        r_i: &Element<Self>,
+        i: u64,
+        j: u64,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let lift = |n: u64| Self::Index::from_u256(n.into()).ok_or(ProverError::OutOfBounds);
+        Self::verify(r_i, lift(i)?, lift(j)?, witness, element)
+    }
+
+    /// Check the witness chain from `r_i` down to `j`, and return the element
+    /// it commits to at `j`
+    fn recover_element(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+    ) -> Result<Element<Self>, ProverError> {
+        let triple =
+            verify::walk::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, witness, |_| {})?;
+        Ok(triple[0].clone())
+    }
+
+    /// Verify that the log's root at `j` was `r_j`, rather than that its
+    /// element there was a given leaf. The witness is the same one that
+    /// proves the element at `j`; its last triple must hash to `r_j`, and
+    /// fails with `RiMismatch` at that triple if it doesn't.
+    fn verify_root_at(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        r_j: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        let triple =
+            verify::walk::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, witness, |_| {})?;
+        if verify::hash_triple::<Self::Digest>(triple) != *r_j {
+            return Err(ProverError::RiMismatch(utils::proof_steps(i, j) - 1));
+        }
+        Ok(())
+    }
+
+    /// Verify a witness from [`Prover::prove_consistency_minimal`]: that
+    /// the log with root `r_b` at size `b` extends the one with root `r_a`
+    /// at size `a`. Fails with `RiMismatch(n)`, `n` being the number of
+    /// triples, if the chain arrives at a root other than `r_a`.
+    fn verify_consistency(
+        r_b: &Element<Self>,
+        b: Self::Index,
+        a: Self::Index,
+        witness: &[Element<Self>],
+        r_a: &Element<Self>,
+    ) -> Result<(), ProverError> {
+        if verify::walk_to_root::<Self::Digest, _>(r_b, &Self::empty_root(), b, a, witness)? != r_a
+        {
+            return Err(ProverError::RiMismatch(witness.len() / 3));
+        }
+        Ok(())
+    }
+
+    /// Verify, returning the branches the verifier followed. These always
+    /// match `proof_decisions(i, j)` for a valid proof.
+    fn verify_decisions(
+        r_i: &Element<Self>,
+        i: Self::Index,
+        j: Self::Index,
+        witness: &[Element<Self>],
+        element: &Element<Self>,
+    ) -> Result<Vec<Branch>, ProverError> {
+        let mut decisions = vec![];
+        let triple =
+            verify::walk::<Self::Digest, _>(r_i, &Self::empty_root(), i, j, witness, |b| {
+                decisions.push(b)
+            })?;
+        verify::check_element::<Self::Digest>(triple, element)?;
+        Ok(decisions)
+    }
 }
@@ -0,0 +1,93 @@
+//! History read from a memory-mapped file, for logs too large to hold in
+//! memory
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+use memmap2::Mmap;
+
+use crate::{Entries, HistoryStore, Index};
+
+/// A read-only history map backed by a memory-mapped file, holding the
+/// value at each index from 0 up as one digest output at offset
+/// `index * output_size`. Lookups borrow straight from the mapping, so only
+/// the pages a proof touches are read from disk.
+pub struct MmapStore<D: Digest, I = U256> {
+    map: Mmap,
+    _marker: PhantomData<(D, I)>,
+}
+
+impl<D: Digest, I: Index> MmapStore<D, I> {
+    /// Map the file at `path`, as written by [`MmapStore::write`]. Fails
+    /// with `InvalidData` if its length is not a whole number of outputs.
+    ///
+    /// The file must not be modified while it is mapped, by this process or
+    /// any other.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read-only, and the caller keeps the file
+        // unmodified while it is mapped
+        let map = unsafe { Mmap::map(&file)? };
+        if !map.chunks_exact(D::output_size()).remainder().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file length is not a whole number of outputs",
+            ));
+        }
+        Ok(Self {
+            map,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Write every entry of `store` to `path` in the layout `open` reads.
+    /// Fails with `InvalidInput` unless the store holds every index from 0
+    /// up, as an unpruned prover's history does.
+    pub fn write(
+        path: impl AsRef<Path>,
+        store: &impl HistoryStore<I, Output<D>>,
+    ) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        for (n, (i, value)) in store.entries().enumerate() {
+            if i.into() != U256::from(n) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "history is not contiguous from 0",
+                ));
+            }
+            out.write_all(value)?;
+        }
+        out.flush()
+    }
+}
+
+impl<D: Digest, I: Index> HistoryStore<I, Output<D>> for MmapStore<D, I> {
+    fn get(&self, i: &I) -> Option<&Output<D>> {
+        let i: U256 = (*i).into();
+        if i >= U256::from(self.len()) {
+            return None;
+        }
+        let size = D::output_size();
+        let start = i.as_usize() * size;
+        Some(Output::<D>::from_slice(&self.map[start..start + size]))
+    }
+
+    fn len(&self) -> usize {
+        self.map.len() / D::output_size()
+    }
+
+    fn entries(&self) -> Entries<'_, I, Output<D>> {
+        Box::new(
+            self.map
+                .chunks_exact(D::output_size())
+                .enumerate()
+                .filter_map(|(n, value)| {
+                    Some((I::from_u256(n.into())?, Output::<D>::from_slice(value)))
+                }),
+        )
+    }
+}
@@ -0,0 +1,58 @@
+//! Witnesses as Merkle-style paths of tagged nodes
+
+use digest::{Digest, Output};
+
+use crate::{utils, Branch, Index, ProverError};
+
+/// The role of a node of a witness triple on the path a proof walks, for
+/// tooling that expects Merkle-style `(node, direction)` paths
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The element inserted at the triple's index
+    Element,
+    /// A root the walk does not follow, like a Merkle sibling. The branch
+    /// says which of the triple's roots it is.
+    Sibling(Branch),
+    /// The root the walk continues to, by this branch
+    Next(Branch),
+}
+
+/// Tag each node of a witness proving `j` against `i` with its role
+pub(crate) fn to_path<D: Digest, I: Index>(
+    i: I,
+    j: I,
+    witness: &[Output<D>],
+) -> Vec<(Output<D>, Direction)> {
+    let mut branches = utils::branches(i, j).into_iter();
+    let mut path = Vec::with_capacity(witness.len());
+    for triple in witness.chunks_exact(3) {
+        let followed = branches.next();
+        let tag = |branch| match followed {
+            Some(b) if b == branch => Direction::Next(branch),
+            _ => Direction::Sibling(branch),
+        };
+        path.push((triple[0].clone(), Direction::Element));
+        path.push((triple[1].clone(), tag(Branch::Prev)));
+        path.push((triple[2].clone(), tag(Branch::Pred)));
+    }
+    path
+}
+
+/// Recover the witness from a path, checking that its tags are the ones
+/// `to_path` gives for `i` and `j`
+pub(crate) fn from_path<D: Digest, I: Index>(
+    i: I,
+    j: I,
+    path: &[(Output<D>, Direction)],
+) -> Result<Vec<Output<D>>, ProverError> {
+    let witness: Vec<_> = path.iter().map(|(node, _)| node.clone()).collect();
+    let tags = to_path::<D, I>(i, j, &witness);
+    if tags
+        .iter()
+        .zip(path)
+        .any(|((_, want), (_, got))| want != got)
+    {
+        return Err(ProverError::MalformedWitness);
+    }
+    Ok(witness)
+}
@@ -0,0 +1,85 @@
+//! Digests that hash a fixed prefix before any input
+
+use digest::{generic_array::GenericArray, FixedOutput, Reset, Update};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Bytes to prepend to every digest computed by a [`Prefixed`] hasher
+pub trait DigestPrefix {
+    const PREFIX: &'static [u8];
+}
+
+/// A hasher that feeds `P::PREFIX` to `D` whenever it is created or reset.
+///
+/// Using `Prefixed<D, P>` as an accumulator's digest applies the prefix
+/// uniformly: to element hashing in `insert_data`, to every root computed by
+/// `insert`, and to every root recomputed by `verify`. Provers and verifiers
+/// must agree on the prefix.
+///
+/// ```
+/// use bigspider_accumulator::{Accumulator, DigestPrefix, Prefixed, SimpleAccumulator};
+///
+/// struct Domain;
+/// impl DigestPrefix for Domain {
+///     const PREFIX: &'static [u8] = b"my-log-v1";
+/// }
+///
+/// let mut acc = SimpleAccumulator::<Prefixed<sha2::Sha256, Domain>>::default();
+/// acc.insert_data("hello");
+/// ```
+pub struct Prefixed<D, P> {
+    inner: D,
+    _prefix: PhantomData<fn() -> P>,
+}
+
+impl<D: Update + Default, P: DigestPrefix> Default for Prefixed<D, P> {
+    fn default() -> Self {
+        let mut inner = D::default();
+        inner.update(P::PREFIX);
+        Self {
+            inner,
+            _prefix: PhantomData,
+        }
+    }
+}
+
+impl<D: Clone, P> Clone for Prefixed<D, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _prefix: PhantomData,
+        }
+    }
+}
+
+impl<D: fmt::Debug, P> fmt::Debug for Prefixed<D, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Prefixed").field(&self.inner).finish()
+    }
+}
+
+impl<D: Update, P> Update for Prefixed<D, P> {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.inner.update(data);
+    }
+}
+
+impl<D: Update + Reset, P: DigestPrefix> Reset for Prefixed<D, P> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.inner.update(P::PREFIX);
+    }
+}
+
+impl<D: Update + FixedOutput, P: DigestPrefix> FixedOutput for Prefixed<D, P> {
+    type OutputSize = D::OutputSize;
+
+    fn finalize_into(self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        self.inner.finalize_into(out);
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        self.inner.finalize_into_reset(out);
+        self.inner.update(P::PREFIX);
+    }
+}
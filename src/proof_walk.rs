@@ -0,0 +1,64 @@
+//! Lazy iteration over the nodes of a proof
+
+use crate::{utils, Element, Index, Prover, ProverError};
+
+/// The nodes of the witness proving `j` against `i`, produced one at a
+/// time by looking them up in the prover's history. Collecting the walk
+/// gives exactly the witness `prove_from(i, j)` returns.
+///
+/// After an error the walk yields nothing more.
+pub struct ProofWalk<'a, P: Prover> {
+    prover: &'a P,
+    /// The index of the next triple, or `None` once the walk has finished
+    i: Option<P::Index>,
+    j: P::Index,
+    triple: [Element<P>; 3],
+    /// The position of the next node within `triple`
+    node: usize,
+}
+
+impl<'a, P: Prover> ProofWalk<'a, P> {
+    pub(crate) fn new(prover: &'a P, i: P::Index, j: P::Index) -> Self {
+        Self {
+            prover,
+            i: Some(i),
+            j,
+            triple: Default::default(),
+            node: 3,
+        }
+    }
+
+    fn triple_at(&self, i: P::Index) -> Result<[Element<P>; 3], ProverError> {
+        let missing = |n: P::Index| ProverError::MissingHistory(n.into());
+        let prev_i = i - P::Index::one();
+        let pred_i = utils::pred(i);
+        let x = self.prover.get_element(&i).ok_or_else(|| missing(i))?;
+        let prev = self.prover.get_r(&prev_i).ok_or_else(|| missing(prev_i))?;
+        let pred = self.prover.get_r(&pred_i).ok_or_else(|| missing(pred_i))?;
+        Ok([x.clone(), prev.clone(), pred.clone()])
+    }
+}
+
+impl<'a, P: Prover> Iterator for ProofWalk<'a, P> {
+    type Item = Result<Element<P>, ProverError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node == 3 {
+            let i = self.i.take()?;
+            // The sentinel at 0 is not an inserted element, and has no triple
+            if self.j > i || self.j.is_zero() {
+                return Some(Err(ProverError::OutOfBounds));
+            }
+            self.triple = match self.triple_at(i) {
+                Ok(triple) => triple,
+                Err(e) => return Some(Err(e)),
+            };
+            if i != self.j {
+                self.i = Some(utils::step(i, self.j).1);
+            }
+            self.node = 0;
+        }
+        self.node += 1;
+        Some(Ok(self.triple[self.node - 1].clone()))
+    }
+}
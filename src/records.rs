@@ -0,0 +1,59 @@
+//! Reading fixed-length records from byte streams
+
+use std::io::{self, Read};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Fill `record` from `reader`. Returns `false` if the input ended cleanly
+/// before the record, and fails if it ended partway through.
+pub(crate) fn read_record(reader: &mut impl Read, record: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < record.len() {
+        match reader.read(&mut record[filled..]) {
+            Ok(0) => return end_of_input(filled),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// The async equivalent of [`read_record`]
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_record_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    record: &mut [u8],
+) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < record.len() {
+        match reader.read(&mut record[filled..]).await {
+            Ok(0) => return end_of_input(filled),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn end_of_input(filled: usize) -> io::Result<bool> {
+    if filled == 0 {
+        Ok(false)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "input ended partway through a record",
+        ))
+    }
+}
+
+pub(crate) fn check_record_len(record_len: usize) -> io::Result<()> {
+    if record_len == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "record length must be non-zero",
+        ));
+    }
+    Ok(())
+}
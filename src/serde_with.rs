@@ -0,0 +1,96 @@
+//! Serde helpers for digest outputs, for use with `#[serde(with = "...")]`.
+//!
+//! [`serde_hex`](crate::serde_hex) and [`serde_b64`](crate::serde_b64)
+//! encode a single output as a string. Their `vec` submodules do the same for
+//! each output in a `Vec`, such as a witness:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Proof {
+//!     #[serde(with = "bigspider_accumulator::serde_b64")]
+//!     element: Output<Sha256>,
+//!     #[serde(with = "bigspider_accumulator::serde_b64::vec")]
+//!     nodes: Vec<Output<Sha256>>,
+//! }
+//! ```
+
+use digest::generic_array::{ArrayLength, GenericArray};
+use serde::de::Error;
+
+fn to_output<N: ArrayLength<u8>, E: Error>(bytes: Vec<u8>) -> Result<GenericArray<u8, N>, E> {
+    if bytes.len() != N::to_usize() {
+        return Err(E::invalid_length(bytes.len(), &"a digest output"));
+    }
+    Ok(GenericArray::clone_from_slice(&bytes))
+}
+
+macro_rules! string_codec {
+    ($(#[$meta:meta])* $name:ident, $encode:path, $decode:path) => {
+        $(#[$meta])*
+        pub mod $name {
+            use digest::generic_array::{ArrayLength, GenericArray};
+            use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+            pub fn serialize<S, N>(output: &GenericArray<u8, N>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+                N: ArrayLength<u8>,
+            {
+                serializer.serialize_str(&$encode(output))
+            }
+
+            pub fn deserialize<'de, D, N>(deserializer: D) -> Result<GenericArray<u8, N>, D::Error>
+            where
+                D: Deserializer<'de>,
+                N: ArrayLength<u8>,
+            {
+                let s = String::deserialize(deserializer)?;
+                super::to_output($decode(&s).map_err(D::Error::custom)?)
+            }
+
+            /// The same encoding, applied to each output in a `Vec`
+            pub mod vec {
+                use super::*;
+
+                pub fn serialize<S, N>(
+                    outputs: &[GenericArray<u8, N>],
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                    N: ArrayLength<u8>,
+                {
+                    serializer.collect_seq(outputs.iter().map(|o| $encode(o)))
+                }
+
+                pub fn deserialize<'de, D, N>(
+                    deserializer: D,
+                ) -> Result<Vec<GenericArray<u8, N>>, D::Error>
+                where
+                    D: Deserializer<'de>,
+                    N: ArrayLength<u8>,
+                {
+                    Vec::<String>::deserialize(deserializer)?
+                        .iter()
+                        .map(|s| super::super::to_output($decode(s).map_err(D::Error::custom)?))
+                        .collect()
+                }
+            }
+        }
+    };
+}
+
+string_codec!(
+    /// Encode digest outputs as lowercase hex strings
+    hex,
+    ::hex::encode,
+    ::hex::decode
+);
+
+string_codec!(
+    /// Encode digest outputs as padded, standard-alphabet base64 strings.
+    /// This is about two thirds the length of hex.
+    b64,
+    ::base64::encode,
+    ::base64::decode
+);
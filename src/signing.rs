@@ -0,0 +1,81 @@
+//! A prover that hands each new root to an external signer
+
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+use std::collections::BTreeMap;
+
+use crate::{Accumulator, Index, SimpleProver};
+
+/// Receives each root a [`SigningProver`] produces, such as an HSM client
+/// that signs it. What it returns is stored against the root's index.
+pub trait RootSink<D: Digest, I = U256> {
+    /// What the sink returns for each root, usually a signature
+    type Signature;
+
+    fn on_root(&mut self, index: I, root: &Output<D>) -> Self::Signature;
+}
+
+/// A [`SimpleProver`] that passes the root of every insert made through it
+/// to a [`RootSink`], and keeps what the sink returns. Signing is not part
+/// of the accumulator: signatures are not hashed, and proofs are unchanged.
+/// The prover can be read, and proofs made, through the wrapper.
+#[derive(Clone, Debug)]
+pub struct SigningProver<D, S, I = U256>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+    S: RootSink<D, I>,
+{
+    prover: SimpleProver<D, I>,
+    sink: S,
+    signatures: BTreeMap<I, S::Signature>,
+}
+
+impl<D, S, I> SigningProver<D, S, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+    S: RootSink<D, I>,
+{
+    /// An empty prover sending its roots to `sink`
+    pub fn new(sink: S) -> Self {
+        Self {
+            prover: Default::default(),
+            sink,
+            signatures: Default::default(),
+        }
+    }
+
+    /// Insert `element`, pass the new root to the sink and store what it
+    /// returns. Returns the new root.
+    pub fn insert(&mut self, element: &Output<D>) -> Output<D> {
+        let root = self.prover.insert(element);
+        let index = self.prover.len();
+        let signature = self.sink.on_root(index, &root);
+        self.signatures.insert(index, signature);
+        root
+    }
+
+    /// What the sink returned for the root at `i`
+    pub fn signature_at(&self, i: impl Into<I>) -> Option<&S::Signature> {
+        self.signatures.get(&i.into())
+    }
+
+    /// Unwrap into the prover, the sink and the signatures by index
+    pub fn into_parts(self) -> (SimpleProver<D, I>, S, BTreeMap<I, S::Signature>) {
+        (self.prover, self.sink, self.signatures)
+    }
+}
+
+impl<D, S, I> std::ops::Deref for SigningProver<D, S, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+    S: RootSink<D, I>,
+{
+    type Target = SimpleProver<D, I>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.prover
+    }
+}
@@ -1,42 +1,277 @@
-use digest::{generic_array::typenum::consts::U32 as TU32, Digest, Output};
+use digest::{Digest, Output};
 use ethers_core::types::U256;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::*;
 
+/// The map type backing a [`SimpleProver`]'s history. With the `fxhash`
+/// feature this is an `FxHashMap`, which is faster for the point lookups
+/// proving does, at the cost of sorting for ordered access.
+pub type History<I, E> = HistoryMap<I, E>;
+
+#[cfg(not(feature = "fxhash"))]
+type HistoryMap<I, E> = BTreeMap<I, E>;
+#[cfg(feature = "fxhash")]
+type HistoryMap<I, E> = rustc_hash::FxHashMap<I, E>;
+
+/// Reserve room for `n` more entries, if the `History` map type supports it
+#[cfg(not(feature = "fxhash"))]
+fn reserve_history<I, E>(_history: &mut History<I, E>, _n: usize) {}
+#[cfg(feature = "fxhash")]
+fn reserve_history<I: Index, E>(history: &mut History<I, E>, n: usize) {
+    history.reserve(n);
+}
+
+/// A state slot that differs between two accumulators, with the value on
+/// each side. See [`SimpleAccumulator::diff`].
+pub type SlotDiff<D> = (usize, Option<Output<D>>, Option<Output<D>>);
+
+/// A prover's element count, state slots, and `elements` and `r` maps. See
+/// [`SimpleProver::into_parts`].
+pub type Parts<D, I = U256> = (
+    I,
+    BTreeMap<usize, Output<D>>,
+    History<I, Output<D>>,
+    History<I, Output<D>>,
+);
+
 #[derive(Default, Clone, Debug)]
-pub struct SimpleAccumulator<D>
+pub struct SimpleAccumulator<D, I = U256>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
-    k: U256,
+    k: I,
     s: BTreeMap<usize, Output<D>>,
+    /// The root at index 0, all zeros unless set by `with_genesis`
+    genesis: Output<D>,
+}
+
+impl<D, I> SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    /// An empty accumulator whose root at index 0 is `genesis` rather than
+    /// `empty_root()`. See [`SimpleProver::with_genesis`].
+    pub fn with_genesis(genesis: Element<Self>) -> Self {
+        Self {
+            genesis,
+            ..Default::default()
+        }
+    }
+
+    /// Compute the root that inserting `element` would produce, without
+    /// modifying the accumulator
+    pub fn peek_insert(&self, element: &Element<Self>) -> Element<Self> {
+        let k = self.k + I::one();
+        let prev = self.get_state(k - I::one()).unwrap();
+        let pred = self
+            .get_state(k - utils::highest_divisor_power_of_2(k))
+            .unwrap();
+
+        let d = Self::get_digest().chain(element).chain(prev).chain(pred);
+        d.finalize()
+    }
+
+    /// Overwrite the root at `i`. Only the current root, at `i == len()`,
+    /// may be replaced: writing the slot of any other index would leave the
+    /// state inconsistent with the length, so this fails with `OutOfBounds`.
+    /// Even then, the new root is not checked against anything; the slot
+    /// map is otherwise not exposed for mutation, as changing it directly
+    /// corrupts every later root.
+    pub fn set_state(&mut self, i: impl Into<I>, root: &Element<Self>) -> Result<(), ProverError> {
+        let i = i.into();
+        if i != self.k || i.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        self.write_slot(i, root);
+        Ok(())
+    }
+
+    fn write_slot(&mut self, i: I, root: &Element<Self>) {
+        self.s.insert(i.trailing_zeros() as usize, root.clone());
+    }
+
+    /// Check that the state slots are the ones `len()` inserts populate.
+    /// Slot `t` is written by every index with `t` trailing zeros, so after
+    /// `k` inserts exactly slots `0..bits(k)` are present. This is cheap, and
+    /// catches state assembled or restored by hand with missing or extra
+    /// slots. It can't detect a slot holding the wrong root.
+    pub fn check_invariants(&self) -> Result<(), ProverError> {
+        let bits = self.k.into().bits();
+        if !self.s.keys().copied().eq(0..bits) {
+            return Err(ProverError::InconsistentState(
+                "state slots don't match the element count",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The state slots that differ between `self` and `other`, in slot
+    /// order, with each side's value. A slot missing on one side is `None`
+    /// there. Slot `n` holds the root of the latest index with `n` trailing
+    /// zeros, so a syncing peer only needs the segments behind the
+    /// differing slots.
+    pub fn diff(&self, other: &Self) -> Vec<SlotDiff<D>> {
+        let slots: BTreeSet<usize> = self.s.keys().chain(other.s.keys()).copied().collect();
+        slots
+            .into_iter()
+            .filter_map(|slot| {
+                let (ours, theirs) = (self.s.get(&slot).cloned(), other.s.get(&slot).cloned());
+                if ours == theirs {
+                    None
+                } else {
+                    Some((slot, ours, theirs))
+                }
+            })
+            .collect()
+    }
+
+    /// Build an accumulator from a stream of `record_len`-byte records,
+    /// hashing and inserting each as `insert_data` does. Fails if the stream
+    /// ends partway through a record.
+    pub fn from_reader(mut reader: impl std::io::Read, record_len: usize) -> std::io::Result<Self> {
+        records::check_record_len(record_len)?;
+        let mut acc = Self::default();
+        let mut record = vec![0; record_len];
+        while records::read_record(&mut reader, &mut record)? {
+            acc.insert_data(&record);
+        }
+        Ok(acc)
+    }
+
+    /// The async equivalent of [`SimpleAccumulator::from_reader`]. Hashing
+    /// doesn't await, so this yields to the runtime every few hundred
+    /// records in case the reader never does.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader<R>(mut reader: R, record_len: usize) -> std::io::Result<Self>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        const YIELD_EVERY: usize = 256;
+
+        records::check_record_len(record_len)?;
+        let mut acc = Self::default();
+        let mut record = vec![0; record_len];
+        let mut read = 0;
+        while records::read_record_async(&mut reader, &mut record).await? {
+            acc.insert_data(&record);
+            read += 1;
+            if read % YIELD_EVERY == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+/// Accumulators are equal if they have the same length and state. This
+/// compares committed state only: `SimpleAccumulator` keeps no history.
+impl<D, I> PartialEq for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.k == other.k && self.s == other.s && self.genesis == other.genesis
+    }
 }
 
+impl<D, I> Eq for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+}
+
+/// Hashes the length and the current root, so accumulators with the same
+/// committed state hash the same, as `PartialEq` requires
+impl<D, I> std::hash::Hash for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.k.hash(state);
+        self.get_root().hash(state);
+    }
+}
 
-impl<D> std::iter::FromIterator<Output<D>> for SimpleAccumulator<D>
+impl<D, I> std::iter::FromIterator<Output<D>> for SimpleAccumulator<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
     fn from_iter<T>(iter: T) -> Self
     where
         T: IntoIterator<Item = Element<Self>>,
     {
         let mut acc = Self::default();
+        acc.extend(iter);
+        acc
+    }
+}
+
+/// Insert each element
+impl<D, I> Extend<Output<D>> for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn extend<T: IntoIterator<Item = Output<D>>>(&mut self, iter: T) {
         for e in iter {
-            acc.insert(&e);
+            self.insert(&e);
         }
-        acc
     }
 }
 
-impl<D> Accumulator for SimpleAccumulator<D>
+/// Hash each item of data and insert it, as `insert_data` does
+impl<'a, D, I> Extend<&'a [u8]> for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn extend<T: IntoIterator<Item = &'a [u8]>>(&mut self, iter: T) {
+        for data in iter {
+            self.insert_data(data);
+        }
+    }
+}
+
+/// `acc += element` inserts `element`, discarding the new root. Use
+/// `insert` to keep it.
+impl<D, I> std::ops::AddAssign<Output<D>> for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn add_assign(&mut self, element: Output<D>) {
+        self.insert(&element);
+    }
+}
+
+/// `acc += &data[..]` hashes and inserts `data` as `insert_data` does,
+/// discarding the new root. Use `insert_data` to keep it.
+impl<'a, D, I> std::ops::AddAssign<&'a [u8]> for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn add_assign(&mut self, data: &'a [u8]) {
+        self.insert_data(data);
+    }
+}
+
+impl<D, I> Accumulator for SimpleAccumulator<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
     type Digest = D;
+    type Index = I;
 
-    fn len(&self) -> U256 {
+    fn len(&self) -> I {
         self.k
     }
 
@@ -44,72 +279,504 @@ where
         &self.s
     }
 
-    fn state_mut(&mut self) -> &mut BTreeMap<usize, Element<Self>> {
-        &mut self.s
+    fn get_state(&self, i: impl Into<I>) -> Option<Element<Self>> {
+        let i = i.into();
+        if i.is_zero() {
+            Some(self.genesis.clone())
+        } else {
+            self.s.get(&(i.trailing_zeros() as usize)).cloned()
+        }
     }
 
     fn insert(&mut self, element: &Element<Self>) -> Element<Self> {
-        self.k = self.k + 1;
-        let prev = self.get_state(self.k - 1).unwrap();
-        let pred = self
-            .get_state(self.k - utils::highest_divisor_power_of_2(self.k))
-            .unwrap();
-
-        let d = Self::get_digest().chain(element).chain(prev).chain(pred);
-        let result = d.finalize();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("insert", k = %(self.k + I::one())).entered();
 
-        self.set_state(self.k, &result);
+        let result = self.peek_insert(element);
+        self.k = self.k + I::one();
+        self.write_slot(self.k, &result);
+        counters::inserted();
         result
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct SimpleProver<D>
+pub struct SimpleProver<D, I = U256>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
-    accumulator: SimpleAccumulator<D>,
-    elements: BTreeMap<U256, Element<SimpleAccumulator<D>>>,
-    r: BTreeMap<U256, Element<SimpleAccumulator<D>>>,
+    accumulator: SimpleAccumulator<D, I>,
+    elements: History<I, Output<D>>,
+    r: History<I, Output<D>>,
+    #[cfg(feature = "bloom")]
+    bloom: Bloom,
+    max_proof_len: Option<usize>,
 }
 
-impl<D> Default for SimpleProver<D>
+impl<D, I> Default for SimpleProver<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
     fn default() -> Self {
         let mut p = Self {
             accumulator: Default::default(),
-            elements: BTreeMap::new(),
-            r: BTreeMap::new(),
+            elements: Default::default(),
+            r: Default::default(),
+            #[cfg(feature = "bloom")]
+            bloom: Default::default(),
+            max_proof_len: None,
+        };
+        p.elements.insert(I::zero(), Self::empty_root());
+        p.r.insert(I::zero(), Self::empty_root());
+        p
+    }
+}
+
+impl<D, I> SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    /// An empty prover with room for `n` elements in its history, so that a
+    /// bulk load doesn't repeatedly grow the maps. This only has an effect
+    /// with the `fxhash` feature, as `BTreeMap` can't preallocate.
+    pub fn with_capacity(n: usize) -> Self {
+        let mut p = Self::default();
+        reserve_history(&mut p.elements, n);
+        reserve_history(&mut p.r, n);
+        p
+    }
+
+    /// An empty prover whose root at index 0 is `genesis` rather than
+    /// `empty_root()`, so that logs of different deployments never share
+    /// roots, even while empty. The genesis is stored as the sentinel in
+    /// both history maps and enters every later root through the skip list.
+    ///
+    /// This is not compatible with the default genesis: every root differs,
+    /// so proofs can't be verified against roots of a log started the other
+    /// way. The genesis is committed to by every `r_i`, but stateless
+    /// verifiers reject only `empty_root()` at later indices unless given
+    /// the genesis, as by `verify_with_genesis`. `genesis()` returns it,
+    /// and exported proofs and packages carry it. Neither `empty_root()`
+    /// nor `is_empty_root` know about it.
+    pub fn with_genesis(genesis: Element<Self>) -> Self {
+        let mut p = Self {
+            accumulator: SimpleAccumulator::with_genesis(genesis.clone()),
+            ..Default::default()
         };
-        p.elements.insert(U256::zero(), Default::default());
-        p.r.insert(U256::zero(), Default::default());
+        p.elements.insert(I::zero(), genesis.clone());
+        p.r.insert(I::zero(), genesis);
         p
     }
+
+    /// Assemble a prover from its parts without replaying any inserts: the
+    /// element count `k`, the accumulator's state slots `s`, and the
+    /// `elements` and `r` history maps. The parts are checked before they are
+    /// accepted:
+    ///
+    /// - both maps hold the same sentinel at index 0, which is taken as the
+    ///   genesis root, and nothing beyond `k`
+    /// - `elements[k]` and `r[k]` are present, and `s` holds `r[k]`
+    /// - every root whose inputs are all present recomputes from them
+    /// - every state slot holds a root from `r` with a matching index, and
+    ///   the slots are those `k` inserts populate
+    pub fn from_parts(
+        k: I,
+        s: BTreeMap<usize, Output<D>>,
+        elements: History<I, Output<D>>,
+        r: History<I, Output<D>>,
+    ) -> Result<Self, ProverError> {
+        let genesis = match (elements.get(&I::zero()), r.get(&I::zero())) {
+            (Some(x), Some(r_0)) if x == r_0 => r_0.clone(),
+            _ => return Err(ProverError::InconsistentState("missing sentinel")),
+        };
+        if elements.keys().chain(r.keys()).any(|i| *i > k) {
+            return Err(ProverError::InconsistentState("history beyond k"));
+        }
+
+        for (i, x) in elements.iter().filter(|(i, _)| !i.is_zero()) {
+            let inputs = (r.get(&(*i - I::one())), r.get(&utils::pred(*i)), r.get(i));
+            if let (Some(prev), Some(pred), Some(r_i)) = inputs {
+                let d = D::new().chain(x).chain(prev).chain(pred).finalize();
+                if d != *r_i {
+                    return Err(ProverError::InconsistentState("root does not recompute"));
+                }
+            }
+        }
+
+        if !k.is_zero() {
+            let r_k = r
+                .get(&k)
+                .ok_or(ProverError::InconsistentState("missing r[k]"))?;
+            if !elements.contains_key(&k) {
+                return Err(ProverError::InconsistentState("missing elements[k]"));
+            }
+            if s.get(&(k.trailing_zeros() as usize)) != Some(r_k) {
+                return Err(ProverError::InconsistentState("state does not hold r[k]"));
+            }
+        }
+        for (slot, v) in s.iter() {
+            let known = r
+                .iter()
+                .any(|(i, r_i)| !i.is_zero() && i.trailing_zeros() as usize == *slot && r_i == v);
+            if !known {
+                return Err(ProverError::InconsistentState("unknown state slot"));
+            }
+        }
+
+        let accumulator = SimpleAccumulator { k, s, genesis };
+        accumulator.check_invariants()?;
+        #[cfg(feature = "bloom")]
+        let bloom = {
+            let mut bloom = Bloom::default();
+            for (_, x) in elements.iter().filter(|(i, _)| !i.is_zero()) {
+                bloom.insert(x);
+            }
+            bloom
+        };
+        Ok(Self {
+            accumulator,
+            elements,
+            r,
+            #[cfg(feature = "bloom")]
+            bloom,
+            max_proof_len: None,
+        })
+    }
+
+    /// Take the prover apart into the parts [`SimpleProver::from_parts`]
+    /// accepts, `(k, s, elements, r)`, moving the maps out rather than
+    /// cloning them. The genesis root stays in both maps as the sentinel.
+    /// The proof length cap is not one of the parts, and the bloom filter is
+    /// rebuilt from `elements`.
+    pub fn into_parts(self) -> Parts<D, I> {
+        let SimpleAccumulator { k, s, .. } = self.accumulator;
+        (k, s, self.elements, self.r)
+    }
+
+    /// Insert `element` unless the log already holds it, returning whether
+    /// it was inserted and the current root. Positions only change on a
+    /// real insert: for a duplicate the prover is left exactly as it was.
+    /// Only retained elements count, so a pruned element is inserted again.
+    /// This is a linear scan of the elements, which with the `bloom` feature
+    /// is skipped for elements the filter has never seen.
+    pub fn insert_if_absent(&mut self, element: &Element<Self>) -> (bool, Element<Self>) {
+        #[cfg(feature = "bloom")]
+        let seen = self.bloom.might_contain(element);
+        #[cfg(not(feature = "bloom"))]
+        let seen = true;
+        if seen && self.index_of(element).is_some() {
+            return (false, self.get_root());
+        }
+        (true, self.insert(element))
+    }
+
+    /// Refuse to generate proofs of more than `max` nodes, failing with
+    /// `ProofTooLarge` instead. `None`, the default, allows any length.
+    /// Proof length grows with `log2(i)`, but a server proving for untrusted
+    /// callers may want a tighter bound. Verifiers can apply the same bound
+    /// with [`Prover::verify_capped`].
+    pub fn set_max_proof_len(&mut self, max: Option<usize>) {
+        self.max_proof_len = max;
+    }
+
+    /// The limit set by [`SimpleProver::set_max_proof_len`]
+    pub fn max_proof_len(&self) -> Option<usize> {
+        self.max_proof_len
+    }
+
+    /// `false` if `element` was definitely never inserted, and `true` if it
+    /// may have been, in which case a proof is needed to be sure. Amended
+    /// elements, rolled back inserts and pruned elements all still test
+    /// `true`.
+    #[cfg(feature = "bloom")]
+    pub fn might_contain(&self, element: &Element<Self>) -> bool {
+        self.bloom.might_contain(element)
+    }
+
+    /// The filter behind [`SimpleProver::might_contain`], to be stored with
+    /// the prover's parts via [`Bloom::to_bytes`]
+    #[cfg(feature = "bloom")]
+    pub fn bloom(&self) -> &Bloom {
+        &self.bloom
+    }
+
+    /// Replace the filter, typically with one stored alongside the parts
+    /// passed to [`SimpleProver::from_parts`]. That rebuilds the filter from
+    /// the elements it is given, so a stored filter is only needed once
+    /// elements have been pruned.
+    #[cfg(feature = "bloom")]
+    pub fn restore_bloom(&mut self, bloom: Bloom) {
+        self.bloom = bloom;
+    }
+
+    /// Drop all history except what `prove_from(target_i, j)` needs for each
+    /// `j` in `js`, along with `r[target_i]` and the sentinel. Inserts after
+    /// this are recorded as usual. Fails with `OutOfBounds`, before dropping
+    /// anything, if `target_i` is beyond `len()` or any `j` is 0 or beyond
+    /// `target_i`.
+    pub fn retain_for_proofs(&mut self, target_i: I, js: &[I]) -> Result<(), ProverError> {
+        if target_i > self.len() || js.iter().any(|j| j.is_zero() || *j > target_i) {
+            return Err(ProverError::OutOfBounds);
+        }
+        let mut elements = BTreeSet::new();
+        let mut r = BTreeSet::new();
+        r.insert(target_i);
+        for &j in js {
+            let mut i = target_i;
+            loop {
+                elements.insert(i);
+                r.insert(i - I::one());
+                r.insert(utils::pred(i));
+                if i == j {
+                    break;
+                }
+                i = utils::step(i, j).1;
+            }
+        }
+        self.elements
+            .retain(|i, _| i.is_zero() || elements.contains(i));
+        self.r.retain(|i, _| i.is_zero() || r.contains(i));
+        Ok(())
+    }
+
+    /// Roll the log back to its first `n` elements, as after a reorg,
+    /// discarding the history above `n`. The state is rebuilt from `r`:
+    /// slot `t` holds the root of the latest index up to `n` with `t`
+    /// trailing zeros, so afterwards the prover matches one that only ever
+    /// had `n` inserts. Fails with `OutOfBounds` if `n > len()`, and with
+    /// `MissingHistory` if a root the state needs was pruned, in both cases
+    /// leaving the prover unchanged. With the `bloom` feature, the discarded
+    /// elements still test `true`.
+    pub fn truncate(&mut self, n: impl Into<I>) -> Result<(), ProverError> {
+        let n = n.into();
+        if n > self.len() {
+            return Err(ProverError::OutOfBounds);
+        }
+        let mut s = BTreeMap::new();
+        let (mut pow, mut slot) = (I::one(), 0);
+        while !n.is_zero() && pow <= n {
+            // the largest odd multiple of `pow` that is at most `n`
+            let base = n & !(pow + (pow - I::one()));
+            let m = if n - base >= pow {
+                base + pow
+            } else {
+                base - pow
+            };
+            let r_m = self
+                .r
+                .get(&m)
+                .ok_or_else(|| ProverError::MissingHistory(m.into()))?;
+            s.insert(slot, r_m.clone());
+            if pow > n - pow {
+                break;
+            }
+            pow = pow + pow;
+            slot += 1;
+        }
+
+        self.elements.retain(|i, _| *i <= n);
+        self.r.retain(|i, _| *i <= n);
+        self.accumulator.k = n;
+        self.accumulator.s = s;
+        Ok(())
+    }
+
+    /// Replace the most recently inserted element, returning the new root.
+    /// Nothing commits to the latest root yet, so only `elements[k]`, `r[k]`
+    /// and the accumulator state need to change. Any proofs or roots handed
+    /// out for the old element are invalidated.
+    pub fn amend_last(&mut self, element: &Element<Self>) -> Result<Element<Self>, ProverError> {
+        let k = self.len();
+        if k.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        let prev = self
+            .get_r(&(k - I::one()))
+            .ok_or_else(|| ProverError::MissingHistory((k - I::one()).into()))?;
+        let pred = self
+            .get_r(&utils::pred(k))
+            .ok_or_else(|| ProverError::MissingHistory(utils::pred(k).into()))?;
+
+        let r = Self::get_digest()
+            .chain(element)
+            .chain(prev)
+            .chain(pred)
+            .finalize();
+        self.accumulator.set_state(k, &r)?;
+        #[cfg(feature = "bloom")]
+        self.bloom.insert(element);
+        self.elements.insert(k, element.clone());
+        self.r.insert(k, r.clone());
+        Ok(r)
+    }
+
+    /// Start staging inserts. They are kept if the returned [`Txn`] is
+    /// committed, and rolled back if it is dropped.
+    pub fn begin(&mut self) -> Txn<'_, D, I> {
+        Txn {
+            k: self.len(),
+            state: self.accumulator.s.clone(),
+            prover: self,
+            committed: false,
+        }
+    }
+}
+
+/// A batch of staged inserts into a [`SimpleProver`]. Dropping it without
+/// calling [`Txn::commit`] restores the prover to its state at
+/// [`SimpleProver::begin`]. The prover can be read through the guard.
+pub struct Txn<'a, D, I = U256>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    prover: &'a mut SimpleProver<D, I>,
+    k: I,
+    state: BTreeMap<usize, Output<D>>,
+    committed: bool,
+}
+
+impl<'a, D, I> Txn<'a, D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    /// Stage an insert, returning the new root
+    pub fn insert(&mut self, element: &Output<D>) -> Output<D> {
+        self.prover.insert(element)
+    }
+
+    /// Keep the staged inserts
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Discard the staged inserts. This is the same as dropping the guard.
+    pub fn rollback(self) {}
+}
+
+impl<'a, D, I> std::ops::Deref for Txn<'a, D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    type Target = SimpleProver<D, I>;
+
+    fn deref(&self) -> &Self::Target {
+        self.prover
+    }
+}
+
+impl<'a, D, I> Drop for Txn<'a, D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let k = self.k;
+        self.prover.elements.retain(|i, _| *i <= k);
+        self.prover.r.retain(|i, _| *i <= k);
+        self.prover.accumulator.k = self.k;
+        self.prover.accumulator.s = std::mem::take(&mut self.state);
+    }
 }
 
-impl<D> std::iter::FromIterator<Output<D>> for SimpleProver<D>
+impl<D, I> std::iter::FromIterator<Output<D>> for SimpleProver<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
     fn from_iter<T>(iter: T) -> Self
     where
         T: IntoIterator<Item = Element<Self>>,
     {
         let mut acc = Self::default();
+        acc.extend(iter);
+        acc
+    }
+}
+
+/// Iterate over the inserted elements in index order, excluding the
+/// sentinel. This is the same as `iter_elements`.
+impl<'a, D, I> IntoIterator for &'a SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    type Item = (I, &'a Output<D>);
+    type IntoIter = Entries<'a, I, Output<D>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_elements()
+    }
+}
+
+/// Insert each element
+impl<D, I> Extend<Output<D>> for SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn extend<T: IntoIterator<Item = Output<D>>>(&mut self, iter: T) {
         for e in iter {
-            acc.insert(&e);
+            self.insert(&e);
         }
-        acc
     }
 }
 
-impl<D> From<SimpleAccumulator<D>> for SimpleProver<D>
+/// Hash each item of data and insert it, as `insert_data` does
+impl<'a, D, I> Extend<&'a [u8]> for SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn extend<T: IntoIterator<Item = &'a [u8]>>(&mut self, iter: T) {
+        for data in iter {
+            self.insert_data(data);
+        }
+    }
+}
+
+/// `acc += element` inserts `element`, discarding the new root. Use
+/// `insert` to keep it.
+impl<D, I> std::ops::AddAssign<Output<D>> for SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn add_assign(&mut self, element: Output<D>) {
+        self.insert(&element);
+    }
+}
+
+/// `acc += &data[..]` hashes and inserts `data` as `insert_data` does,
+/// discarding the new root. Use `insert_data` to keep it.
+impl<'a, D, I> std::ops::AddAssign<&'a [u8]> for SimpleProver<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
-    fn from(accumulator: SimpleAccumulator<D>) -> Self {
+    fn add_assign(&mut self, data: &'a [u8]) {
+        self.insert_data(data);
+    }
+}
+
+/// Wrap an accumulator in a prover. The accumulator retains no history, so
+/// the resulting prover can't prove any of the elements inserted before the
+/// conversion. To get a fully functional prover, replay the elements into a
+/// new `SimpleProver` instead.
+impl<D, I> From<SimpleAccumulator<D, I>> for SimpleProver<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn from(accumulator: SimpleAccumulator<D, I>) -> Self {
         Self {
             accumulator,
             ..Default::default()
@@ -117,13 +784,26 @@ where
     }
 }
 
-impl<D> Accumulator for SimpleProver<D>
+/// Discard the proving history, keeping only the accumulator state
+impl<D, I> From<SimpleProver<D, I>> for SimpleAccumulator<D, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn from(prover: SimpleProver<D, I>) -> Self {
+        prover.accumulator
+    }
+}
+
+impl<D, I> Accumulator for SimpleProver<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
     type Digest = D;
+    type Index = I;
 
-    fn len(&self) -> U256 {
+    fn len(&self) -> I {
         self.accumulator.len()
     }
 
@@ -131,99 +811,104 @@ where
         &self.accumulator.s
     }
 
-    fn state_mut(&mut self) -> &mut BTreeMap<usize, Element<Self>> {
-        self.accumulator.state_mut()
+    fn get_state(&self, i: impl Into<I>) -> Option<Element<Self>> {
+        self.accumulator.get_state(i)
     }
 
     fn insert(&mut self, element: &Element<Self>) -> Element<Self> {
         let r = self.accumulator.insert(element);
-        self.elements.insert(self.len(), *element);
-        self.r.insert(self.len(), r);
+        #[cfg(feature = "bloom")]
+        self.bloom.insert(element);
+        self.elements.insert(self.len(), element.clone());
+        self.r.insert(self.len(), r.clone());
         r
     }
 }
 
-impl<D> Prover for SimpleProver<D>
+impl<D, I> Prover for SimpleProver<D, I>
 where
-    D: Digest<OutputSize = TU32> + Clone + Default,
+    D: Digest + Clone + Default,
+    I: Index,
 {
-    fn elements(&self) -> &BTreeMap<U256, Element<Self>> {
+    type History = History<I, Output<D>>;
+
+    fn elements(&self) -> &Self::History {
         &self.elements
     }
 
-    fn r(&self) -> &BTreeMap<U256, Element<Self>> {
+    fn r(&self) -> &Self::History {
         &self.r
     }
 
     fn prove_from(
         &self,
-        i: impl Into<U256>,
-        j: impl Into<U256>,
+        i: impl Into<I>,
+        j: impl Into<I>,
     ) -> Result<Vec<Element<Self>>, ProverError> {
-        let (i, j) = (i.into(), j.into());
-        if j > i {
-            return Err(ProverError::OutOfBounds);
-        };
+        let mut witness = vec![];
+        self.prove_into(i, j, &mut witness)?;
+        Ok(witness)
+    }
 
-        let pred_i = utils::pred(i);
+    fn prove_into(
+        &self,
+        i: impl Into<I>,
+        j: impl Into<I>,
+        buf: &mut Vec<Element<Self>>,
+    ) -> Result<(), ProverError> {
+        let (i, j) = (i.into(), j.into());
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "prove",
+            %i,
+            %j,
+            witness_len = tracing::field::Empty
+        )
+        .entered();
 
-        let elements_i = self.get_element(&i).ok_or(ProverError::MissingHistory(i))?;
-        let prev = self
-            .get_r(&(i - 1))
-            .ok_or_else(|| ProverError::MissingHistory(i - 1))?;
-        let pred = self
-            .get_r(&pred_i)
-            .ok_or(ProverError::MissingHistory(pred_i))?;
+        if let Some(max) = self.max_proof_len {
+            let len = 3 * utils::proof_steps(i, j);
+            if len > max {
+                return Err(ProverError::ProofTooLarge(len));
+            }
+        }
 
-        let mut witness = vec![*elements_i, *prev, *pred];
-        if i > j {
-            if pred_i >= j {
-                witness.extend(self.prove_from(pred_i, j)?);
-            } else {
-                witness.extend(self.prove_from(i - 1, j)?);
+        let start = buf.len();
+        for node in self.proof_walk(i, j) {
+            match node {
+                Ok(node) => buf.push(node),
+                Err(e) => {
+                    buf.truncate(start);
+                    return Err(e);
+                }
             }
         }
 
-        Ok(witness)
+        #[cfg(feature = "tracing")]
+        span.record("witness_len", &(buf.len() - start));
+        counters::proved((buf.len() - start) * D::output_size());
+        Ok(())
     }
 
     fn verify(
         r_i: &Element<Self>,
-        i: U256,
-        j: U256,
+        i: I,
+        j: I,
         witness: &[Element<Self>],
         element: &Element<Self>,
     ) -> Result<(), ProverError> {
-        assert!(j <= i);
-        if witness.len() < 3 {
-            return Err(ProverError::WitnessTooShort);
-        }
-
-        let (x_i, r_prev, r_pred) = (&witness[0], &witness[1], &witness[2]);
-        let d = Self::get_digest().chain(x_i).chain(r_prev).chain(r_pred);
-        let d = d.finalize();
-        if d != *r_i {
-            return Err(ProverError::RiMismatch);
-        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("verify", %i, %j, witness_len = witness.len()).entered();
 
-        if i == j {
-            if x_i == element {
-                return Ok(());
-            } else {
-                return Err(ProverError::XiMismatch);
-            }
-        }
-        if utils::pred(i) >= j {
-            return Self::verify(r_pred, utils::pred(i), j, &witness[3..], element);
-        }
-        Self::verify(r_prev, i - 1, j, &witness[3..], element)
+        let result = Self::verify_with_genesis(&Self::empty_root(), r_i, i, j, witness, element);
+        counters::verified(&result);
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sha2;
 
     #[test]
     fn it_works() {
@@ -247,4 +932,2629 @@ mod tests {
         SimpleProver::<sha2::Sha256>::verify(&root_4, 4.into(), 4.into(), &witness, &elements[3])
             .unwrap();
     }
+
+    #[test]
+    fn fresh_root_is_empty() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        assert!(SimpleProver::<sha2::Sha256>::is_empty_root(
+            &prover.get_root()
+        ));
+        assert!(SimpleAccumulator::<sha2::Sha256>::is_empty_root(
+            &SimpleAccumulator::<sha2::Sha256>::default().get_root()
+        ));
+
+        prover.insert_data("data");
+        assert!(!SimpleProver::<sha2::Sha256>::is_empty_root(
+            &prover.get_root()
+        ));
+    }
+
+    #[test]
+    fn insert_chunks_matches_concatenation() {
+        let mut a = SimpleAccumulator::<sha2::Sha256>::default();
+        let mut b = SimpleAccumulator::<sha2::Sha256>::default();
+        assert_eq!(a.insert_chunks(["ab", "cd"]), b.insert_data("abcd"));
+        assert_eq!(a.get_root(), b.get_root());
+    }
+
+    #[test]
+    fn prover_into_accumulator() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..7).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let len = prover.len();
+
+        let mut acc: SimpleAccumulator<_> = prover.clone().into();
+        assert_eq!(acc.get_root(), root);
+        assert_eq!(acc.len(), len);
+
+        let mut prover = prover;
+        let element = sha2::Sha256::digest(b"next");
+        assert_eq!(acc.insert(&element), prover.insert(&element));
+    }
+
+    #[test]
+    fn insert_owned_matches_insert_data() {
+        let data: Vec<Vec<u8>> = vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()];
+        let mut a = SimpleProver::<sha2::Sha256>::default();
+        let mut b = SimpleProver::<sha2::Sha256>::default();
+
+        assert_eq!(a.insert_owned(data[0].clone()), b.insert_data(&data[0]));
+        let root = a.insert_owned_many(data[1..].to_vec());
+        b.insert_data(&data[1]);
+        assert_eq!(root, b.insert_data(&data[2]));
+        assert_eq!(a.get_root(), b.get_root());
+    }
+
+    #[test]
+    fn counts_exclude_sentinel() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        assert!(prover.is_empty());
+        assert_eq!(prover.len(), U256::zero());
+        assert_eq!(prover.iter_elements().count(), 0);
+
+        prover.insert_data("one");
+        assert!(!prover.is_empty());
+        assert_eq!(prover.len(), U256::one());
+        assert_eq!(prover.iter_elements().count(), 1);
+        let acc: SimpleAccumulator<_> = prover.clone().into();
+        assert_eq!(acc.len(), U256::one());
+
+        for i in 0u8..20 {
+            prover.insert_data([i]);
+        }
+        assert_eq!(prover.len(), U256::from(21));
+        assert_eq!(prover.iter_elements().count(), 21);
+        assert!(prover.iter_elements().all(|(i, _)| !i.is_zero()));
+    }
+
+    #[test]
+    fn peek_insert_matches_insert() {
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        for i in 0u8..10 {
+            let element = sha2::Sha256::digest(&[i]);
+            let before = acc.get_root();
+            let peeked = acc.peek_insert(&element);
+            assert_eq!(acc.get_root(), before);
+            assert_eq!(acc.insert(&element), peeked);
+        }
+    }
+
+    #[test]
+    fn proof_hash_count_matches_witness() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        for i in 1u64..=40 {
+            for j in 1..=i {
+                let witness = prover.prove_from(i, j).unwrap();
+                assert_eq!(
+                    SimpleProver::<sha2::Sha256>::proof_hash_count(i, j),
+                    witness.len() / 3
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prove_and_verify_decisions_match() {
+        let prover: SimpleProver<sha2::Sha256> = (0u16..300)
+            .map(|i| sha2::Sha256::digest(&i.to_be_bytes()))
+            .collect();
+        let len = prover.len().as_u64();
+
+        // xorshift, for reproducible pseudo-random indices
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound + 1
+        };
+        for _ in 0..200 {
+            let i = next(len);
+            let j = next(i);
+            let (i, j) = (U256::from(i), U256::from(j));
+            let root = prover.get_r(&i).unwrap();
+
+            let witness = prover.prove_from(i, j).unwrap();
+            let decisions = SimpleProver::<sha2::Sha256>::verify_decisions(
+                root,
+                i,
+                j,
+                &witness,
+                prover.get_element(&j).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                decisions,
+                SimpleProver::<sha2::Sha256>::proof_decisions(i, j)
+            );
+            assert_eq!(decisions.len() + 1, witness.len() / 3);
+        }
+    }
+
+    #[test]
+    fn anchored_proofs_are_shorter() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..100).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let j = U256::from(90);
+        let element = prover.get_element(&j).unwrap();
+        let full = prover.prove(j).unwrap();
+
+        let checkpoints = [U256::from(64), U256::from(96)];
+        let proof = prover.prove_anchored(100, j, &checkpoints).unwrap();
+        assert_eq!(proof.checkpoint, U256::from(96));
+        assert!(proof.witness.len() < full.len());
+
+        let checkpoint_root = prover.get_r(&proof.checkpoint).unwrap();
+        SimpleProver::<sha2::Sha256>::verify_anchored(checkpoint_root, j, &proof, element).unwrap();
+        assert!(SimpleProver::<sha2::Sha256>::verify_anchored(
+            &prover.get_root(),
+            j,
+            &proof,
+            element
+        )
+        .is_err());
+
+        // no usable checkpoint falls back to the full proof
+        let proof = prover.prove_anchored(100, j, &checkpoints[..1]).unwrap();
+        assert_eq!(proof.checkpoint, U256::from(100));
+        assert_eq!(proof.witness, full);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn emits_tracing_spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        #[derive(Default)]
+        struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+        impl tracing::Subscriber for SpanNames {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+                Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let subscriber = SpanNames::default();
+        let names = subscriber.0.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut prover = SimpleProver::<sha2::Sha256>::default();
+            prover.insert_data("a");
+            prover.insert_data("b");
+            let witness = prover.prove(1).unwrap();
+            SimpleProver::<sha2::Sha256>::verify(
+                &prover.get_root(),
+                prover.len(),
+                1.into(),
+                &witness,
+                prover.get_element(&1.into()).unwrap(),
+            )
+            .unwrap();
+        });
+
+        assert_eq!(
+            *names.lock().unwrap(),
+            vec!["insert", "insert", "prove", "verify"]
+        );
+    }
+
+    #[test]
+    fn recovers_committed_element() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..12).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        for j in 1u64..=12 {
+            let witness = prover.prove(j).unwrap();
+            let recovered = SimpleProver::<sha2::Sha256>::recover_element(
+                &root,
+                prover.len(),
+                j.into(),
+                &witness,
+            )
+            .unwrap();
+            assert_eq!(recovered, sha2::Sha256::digest(&[j as u8 - 1]));
+        }
+    }
+
+    fn check_index<I: Index + From<u8>>() {
+        let elements: Vec<_> = (0u8..33).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let reference: SimpleProver<sha2::Sha256> = elements.iter().copied().collect();
+        let prover: SimpleProver<sha2::Sha256, I> = elements.iter().copied().collect();
+        assert_eq!(prover.get_root(), reference.get_root());
+        assert_eq!(prover.len(), I::from(33));
+
+        for i in 1u8..=33 {
+            let r_i = prover.get_r(&I::from(i)).unwrap();
+            for j in 1..=i {
+                let witness = prover.prove_from(I::from(i), I::from(j)).unwrap();
+                assert_eq!(witness, reference.prove_from(i, j).unwrap());
+                SimpleProver::<sha2::Sha256, I>::verify(
+                    r_i,
+                    I::from(i),
+                    I::from(j),
+                    &witness,
+                    &elements[j as usize - 1],
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn u64_index() {
+        check_index::<u64>();
+    }
+
+    #[test]
+    fn u128_index() {
+        check_index::<u128>();
+    }
+
+    #[test]
+    fn prove_into_matches_prove_from() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..50).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let mut buf = vec![];
+        for j in 1u8..=50 {
+            buf.clear();
+            prover.prove_into(50, j, &mut buf).unwrap();
+            assert_eq!(buf, prover.prove_from(50, j).unwrap());
+        }
+
+        // appends, and leaves the buffer untouched on error
+        let len = buf.len();
+        prover.prove_into(50, 1, &mut buf).unwrap();
+        assert_eq!(buf[len..], prover.prove_from(50, 1).unwrap()[..]);
+        let len = buf.len();
+        assert!(prover.prove_into(51, 1, &mut buf).is_err());
+        assert_eq!(buf.len(), len);
+    }
+
+    #[test]
+    fn subtree_roots_follow_pred() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        assert_eq!(prover.subtree_root(0), None);
+        assert_eq!(prover.subtree_root(40), None);
+        for i in 1u8..=20 {
+            let pred = U256::from(i) - (i & i.wrapping_neg());
+            assert_eq!(prover.subtree_root(i), prover.root_at(pred));
+            assert!(prover.subtree_root(i).is_some());
+        }
+        // 8 = 0b1000 skips back to the sentinel, 12 = 0b1100 to 8
+        assert_eq!(
+            prover.subtree_root(8),
+            Some(SimpleProver::<sha2::Sha256>::empty_root())
+        );
+        assert_eq!(prover.subtree_root(12), prover.root_at(8));
+    }
+
+    #[test]
+    fn reports_mismatch_offset() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let mut witness = prover.prove(3).unwrap();
+        assert!(witness.len() >= 9);
+        witness[4] = Default::default();
+        let result = SimpleProver::<sha2::Sha256>::verify(
+            &prover.get_root(),
+            20.into(),
+            3.into(),
+            &witness,
+            prover.get_element(&3.into()).unwrap(),
+        );
+        assert!(matches!(result, Err(ProverError::RiMismatch(1))));
+    }
+
+    #[test]
+    fn generic_root_bytes_and_ingest() {
+        fn ingest_all<A: Accumulator>(acc: &mut A, data: &[&[u8]]) -> Vec<u8> {
+            for d in data {
+                acc.ingest(d);
+            }
+            acc.root_bytes()
+        }
+
+        let data: &[&[u8]] = &[b"a", b"b", b"c"];
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        let root = ingest_all(&mut acc, data);
+        assert_eq!(root, ingest_all(&mut prover, data));
+        assert_eq!(root, acc.root_bytes());
+
+        let mut accs: Vec<Box<dyn DynAccumulator>> = vec![
+            Box::new(SimpleAccumulator::<sha2::Sha256>::default()),
+            Box::new(SimpleProver::<sha2::Sha256>::default()),
+        ];
+        for acc in accs.iter_mut() {
+            for d in data {
+                acc.dyn_ingest(d);
+            }
+            assert_eq!(acc.dyn_root_bytes(), root);
+        }
+    }
+
+    #[test]
+    fn retains_only_requested_proofs() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let full = prover.clone();
+        prover
+            .retain_for_proofs(40.into(), &[3.into(), 17.into()])
+            .unwrap();
+        assert!(prover.elements().len() < full.elements().len());
+        assert!(prover.r().len() < full.r().len());
+
+        for j in [3, 17].iter() {
+            assert_eq!(
+                prover.prove_from(40, *j).unwrap(),
+                full.prove_from(40, *j).unwrap()
+            );
+        }
+        assert!(matches!(
+            prover.prove_from(40, 35),
+            Err(ProverError::MissingHistory(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_retaining_out_of_bounds_proofs() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..8).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let full = prover.clone();
+        for (target_i, j) in [(8u8, 0u8), (8, 9), (9, 3)] {
+            assert!(matches!(
+                prover.retain_for_proofs(target_i.into(), &[3.into(), j.into()]),
+                Err(ProverError::OutOfBounds)
+            ));
+            assert_eq!(prover.elements(), full.elements());
+            assert_eq!(prover.r(), full.r());
+        }
+    }
+
+    #[test]
+    fn amends_last_element() {
+        let elements: Vec<_> = (0u8..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let mut prover: SimpleProver<sha2::Sha256> = elements.iter().copied().collect();
+        let old = elements[9];
+        let new = sha2::Sha256::digest(b"amended");
+
+        let root = prover.amend_last(&new).unwrap();
+        let mut expected: SimpleProver<sha2::Sha256> = elements[..9].iter().copied().collect();
+        assert_eq!(root, expected.insert(&new));
+        assert_eq!(prover.get_root(), root);
+
+        let witness = prover.prove(10).unwrap();
+        SimpleProver::<sha2::Sha256>::verify(&root, 10.into(), 10.into(), &witness, &new).unwrap();
+        assert!(matches!(
+            SimpleProver::<sha2::Sha256>::verify(&root, 10.into(), 10.into(), &witness, &old),
+            Err(ProverError::XiMismatch)
+        ));
+
+        // the rest of the history still proves
+        let witness = prover.prove(4).unwrap();
+        SimpleProver::<sha2::Sha256>::verify(&root, 10.into(), 4.into(), &witness, &elements[3])
+            .unwrap();
+
+        assert!(SimpleProver::<sha2::Sha256>::default()
+            .amend_last(&new)
+            .is_err());
+    }
+
+    #[test]
+    fn insert_bytes_checks_length() {
+        let element = sha2::Sha256::digest(b"element");
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        let mut expected = acc.clone();
+        assert_eq!(
+            acc.insert_bytes(&element).unwrap(),
+            expected.insert(&element)
+        );
+
+        for len in [0, 31, 33, 64].iter() {
+            assert!(matches!(
+                acc.insert_bytes(&vec![0; *len]),
+                Err(ProverError::InvalidElementLength(l)) if l == *len
+            ));
+        }
+        assert_eq!(acc.len(), 1.into());
+    }
+
+    #[test]
+    fn txn_rolls_back_on_drop() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..5).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let before = prover.clone();
+
+        {
+            let mut txn = prover.begin();
+            for i in 5u8..8 {
+                txn.insert(&sha2::Sha256::digest(&[i]));
+            }
+            assert_eq!(txn.len(), 8.into());
+        }
+        assert_eq!(prover.len(), before.len());
+        assert_eq!(prover.get_root(), before.get_root());
+        assert_eq!(prover.elements(), before.elements());
+        assert_eq!(prover.r(), before.r());
+        assert_eq!(prover.state(), before.state());
+
+        let mut txn = prover.begin();
+        let root = txn.insert(&sha2::Sha256::digest(&[5]));
+        txn.commit();
+        assert_eq!(prover.get_root(), root);
+        assert_eq!(prover.len(), 6.into());
+    }
+
+    #[test]
+    fn witness_for_raw_data() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        for data in ["alpha", "beta", "gamma", "delta"].iter() {
+            prover.insert_data(data);
+        }
+
+        let (j, witness) = prover.witness_for_data(prover.len(), "gamma").unwrap();
+        assert_eq!(j, 3.into());
+        SimpleProver::<sha2::Sha256>::verify(
+            &prover.get_root(),
+            prover.len(),
+            j,
+            &witness,
+            &sha2::Sha256::digest(b"gamma"),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            prover.witness_for_data(prover.len(), "epsilon"),
+            Err(ProverError::ElementNotFound)
+        ));
+    }
+
+    #[test]
+    fn history_entries_are_ordered() {
+        let elements: Vec<_> = (0u8..100).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let prover: SimpleProver<sha2::Sha256> = elements.iter().copied().collect();
+
+        let iterated: Vec<_> = prover.iter_elements().map(|(i, x)| (i, *x)).collect();
+        let expected: Vec<_> = (1u8..=100).map(U256::from).zip(elements).collect();
+        assert_eq!(iterated, expected);
+
+        let roots: Vec<_> = prover.r().entries().collect();
+        assert_eq!(roots.len(), 101);
+        assert!(roots.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(*roots.last().unwrap().1, prover.get_root());
+    }
+
+    #[test]
+    fn verify_job_matches_verify() {
+        let elements: Vec<_> = (0u8..100).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let prover: SimpleProver<sha2::Sha256> = elements.iter().copied().collect();
+        let root = prover.get_root();
+
+        let run = |j: u8, witness: &[Output<sha2::Sha256>], element| {
+            let mut job =
+                VerifyJob::<sha2::Sha256>::new(&root, 100.into(), j.into(), witness, element, 2);
+            let mut last = job.progress();
+            assert_eq!(last, 0.0);
+            loop {
+                if let Some(result) = job.step() {
+                    assert_eq!(job.progress(), 1.0);
+                    return result;
+                }
+                assert!(job.progress() > last && job.progress() < 1.0);
+                last = job.progress();
+            }
+        };
+
+        for j in 1u8..=100 {
+            let witness = prover.prove(j).unwrap();
+            let element = &elements[j as usize - 1];
+            assert!(run(j, &witness, element).is_ok());
+
+            let wrong = &elements[j as usize % 100];
+            let expected =
+                SimpleProver::<sha2::Sha256>::verify(&root, 100.into(), j.into(), &witness, wrong);
+            assert_eq!(run(j, &witness, wrong).is_err(), expected.is_err());
+            assert!(expected.is_err());
+        }
+    }
+
+    #[test]
+    fn fold_roots_commits_to_history() {
+        let a: SimpleProver<sha2::Sha256> = (0u8..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let b: SimpleProver<sha2::Sha256> = (0u8..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let c: SimpleProver<sha2::Sha256> =
+            (0u8..10).map(|i| sha2::Sha256::digest(&[i / 9])).collect();
+        assert_eq!(a.fold_roots().unwrap(), b.fold_roots().unwrap());
+        assert_ne!(a.fold_roots().unwrap(), c.fold_roots().unwrap());
+        assert_ne!(a.fold_roots().unwrap(), a.get_root());
+
+        // it's a chain, so it's not a hash of the roots in order
+        let mut d = sha2::Sha256::new();
+        a.r()
+            .entries()
+            .filter(|(i, _)| !i.is_zero())
+            .for_each(|(_, r)| d.update(r));
+        assert_ne!(a.fold_roots().unwrap(), d.finalize());
+
+        // a prefix folds to a different value
+        let mut prefix = a.clone();
+        prefix.truncate(9).unwrap();
+        assert_ne!(prefix.fold_roots().unwrap(), a.fold_roots().unwrap());
+
+        // and a pruned history can't be folded at all
+        let mut pruned = a.clone();
+        pruned
+            .retain_for_proofs(U256::from(10), &[U256::from(10)])
+            .unwrap();
+        let gap = (1u64..=10).find(|i| pruned.root_at(*i).is_none()).unwrap();
+        assert!(matches!(
+            pruned.fold_roots(),
+            Err(ProverError::MissingHistory(i)) if i == gap.into()
+        ));
+    }
+
+    #[test]
+    fn sentinel_is_out_of_bounds() {
+        let empty = SimpleProver::<sha2::Sha256>::default();
+        assert!(matches!(
+            empty.prove_from(0, 0),
+            Err(ProverError::OutOfBounds)
+        ));
+        let root = SimpleProver::<sha2::Sha256>::empty_root();
+        assert!(matches!(
+            SimpleProver::<sha2::Sha256>::verify(&root, 0.into(), 0.into(), &[], &root),
+            Err(ProverError::OutOfBounds)
+        ));
+
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..5).map(|i| sha2::Sha256::digest(&[i])).collect();
+        assert!(matches!(
+            prover.prove_from(5, 0),
+            Err(ProverError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn finds_first_divergence() {
+        let shared: Vec<_> = (0u8..37).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let mut a: SimpleProver<sha2::Sha256> = shared.iter().copied().collect();
+        let mut b = a.clone();
+        assert_eq!(a.first_divergence(&b), None);
+        assert!(a.prove_divergence(&b).unwrap().is_none());
+
+        let x = sha2::Sha256::digest(b"x");
+        let y = sha2::Sha256::digest(b"y");
+        a.insert(&x);
+        b.insert(&y);
+        for i in 0u8..20 {
+            a.insert(&sha2::Sha256::digest(&[i]));
+            b.insert(&sha2::Sha256::digest(&[i]));
+        }
+        b.insert(&x);
+
+        assert_eq!(a.first_divergence(&b), Some(38.into()));
+        assert_eq!(b.first_divergence(&a), Some(38.into()));
+        let divergence = a.prove_divergence(&b).unwrap().unwrap();
+        assert_eq!(divergence.index, 38.into());
+        SimpleProver::<sha2::Sha256>::verify(
+            &a.get_root(),
+            a.len(),
+            38.into(),
+            &divergence.ours,
+            &x,
+        )
+        .unwrap();
+        SimpleProver::<sha2::Sha256>::verify(
+            &b.get_root(),
+            b.len(),
+            38.into(),
+            &divergence.theirs,
+            &y,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn prefixed_digest_separates_domains() {
+        struct Domain;
+        impl DigestPrefix for Domain {
+            const PREFIX: &'static [u8] = b"domain";
+        }
+        type D = Prefixed<sha2::Sha256, Domain>;
+
+        assert_eq!(D::digest(b"data"), sha2::Sha256::digest(b"domaindata"));
+        let mut d = D::new();
+        d.update(b"data");
+        assert_eq!(d.finalize_reset(), D::digest(b"data"));
+        assert_eq!(d.finalize(), D::digest(b""));
+
+        let mut plain = SimpleProver::<sha2::Sha256>::default();
+        let mut prefixed = SimpleProver::<D>::default();
+        for i in 0u8..10 {
+            let element = sha2::Sha256::digest(&[i]);
+            assert_ne!(plain.insert(&element), prefixed.insert(&element));
+        }
+
+        let witness = prefixed.prove(3).unwrap();
+        let element = sha2::Sha256::digest(&[2]);
+        SimpleProver::<D>::verify(
+            &prefixed.get_root(),
+            10.into(),
+            3.into(),
+            &witness,
+            &element,
+        )
+        .unwrap();
+        assert!(SimpleProver::<sha2::Sha256>::verify(
+            &prefixed.get_root(),
+            10.into(),
+            3.into(),
+            &witness,
+            &element
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serde_round_trips_witness() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Wire {
+            i: u64,
+            j: u64,
+            #[serde(with = "crate::serde_b64")]
+            root: Output<sha2::Sha256>,
+            #[serde(with = "crate::serde_hex")]
+            element: Output<sha2::Sha256>,
+            #[serde(with = "crate::serde_b64::vec")]
+            nodes: Vec<Output<sha2::Sha256>>,
+        }
+
+        let elements: Vec<_> = (0u8..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let prover: SimpleProver<sha2::Sha256> = elements.iter().copied().collect();
+        let wire = Wire {
+            i: 10,
+            j: 3,
+            root: prover.get_root(),
+            element: elements[2],
+            nodes: prover.prove(3).unwrap(),
+        };
+
+        let json = serde_json::to_string(&wire).unwrap();
+        assert!(json.contains(&format!("\"{}\"", base64::encode(wire.root))));
+        assert!(json.contains(&format!("\"{}\"", hex::encode(wire.element))));
+        let decoded: Wire = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, wire);
+        SimpleProver::<sha2::Sha256>::verify(
+            &decoded.root,
+            decoded.i.into(),
+            decoded.j.into(),
+            &decoded.nodes,
+            &decoded.element,
+        )
+        .unwrap();
+
+        let short = json.replace(&base64::encode(wire.root), &base64::encode([0u8; 31]));
+        assert!(serde_json::from_str::<Wire>(&short).is_err());
+    }
+
+    #[test]
+    fn from_parts_checks_consistency() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let (k, s, elements, r) = (
+            prover.len(),
+            prover.state().clone(),
+            prover.elements().clone(),
+            prover.r().clone(),
+        );
+
+        let rebuilt =
+            SimpleProver::<sha2::Sha256>::from_parts(k, s.clone(), elements.clone(), r.clone())
+                .unwrap();
+        assert_eq!(rebuilt.get_root(), prover.get_root());
+        assert_eq!(rebuilt.prove(7).unwrap(), prover.prove(7).unwrap());
+
+        let inconsistent = |k, s, elements, r| {
+            matches!(
+                SimpleProver::<sha2::Sha256>::from_parts(k, s, elements, r),
+                Err(ProverError::InconsistentState(_))
+            )
+        };
+
+        let mut tampered = elements.clone();
+        tampered.insert(5.into(), sha2::Sha256::digest(b"tampered"));
+        assert!(inconsistent(k, s.clone(), tampered, r.clone()));
+
+        let mut no_sentinel = r.clone();
+        no_sentinel.remove(&0.into());
+        assert!(inconsistent(k, s.clone(), elements.clone(), no_sentinel));
+
+        assert!(inconsistent(
+            19.into(),
+            s.clone(),
+            elements.clone(),
+            r.clone()
+        ));
+
+        let mut bad_slot = s;
+        bad_slot.insert(7, sha2::Sha256::digest(b"slot"));
+        assert!(inconsistent(k, bad_slot, elements, r));
+    }
+
+    #[test]
+    fn latest_proof_bundle_verifies() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        assert!(prover.latest_proof_bundle().is_err());
+        for i in 0u8..12 {
+            prover.insert_data([i]);
+        }
+
+        let bundle = prover.latest_proof_bundle().unwrap();
+        assert_eq!(bundle.i, 12.into());
+        assert_eq!(bundle.j, 12.into());
+        assert_eq!(bundle.element, sha2::Sha256::digest(&[11]));
+        bundle.verify().unwrap();
+
+        #[cfg(feature = "serialize")]
+        {
+            let json = serde_json::to_string(&bundle).unwrap();
+            let decoded: ProofBundle<sha2::Sha256> = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.root, bundle.root);
+            assert_eq!((decoded.i, decoded.j), (bundle.i, bundle.j));
+            assert_eq!(decoded.element, bundle.element);
+            assert_eq!(decoded.witness, bundle.witness);
+            decoded.verify().unwrap();
+        }
+
+        let mut forged = bundle;
+        forged.element = sha2::Sha256::digest(b"forged");
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn canonical_witness_round_trips() {
+        type D = sha2::Sha256;
+        let elements: Vec<_> = (0u8..30).map(|i| D::digest(&[i])).collect();
+        let prover: SimpleProver<D> = elements.iter().copied().collect();
+        let witness = prover.prove_from(30, 7).unwrap();
+
+        let bytes = encode_canonical_witness::<D, U256>(30.into(), 7.into(), &witness);
+        assert_eq!(bytes.len(), 64 + 32 * witness.len());
+        let (i, j, nodes) = parse_canonical_witness::<D, U256>(&bytes).unwrap();
+        assert_eq!((i, j), (30.into(), 7.into()));
+        assert_eq!(nodes, witness);
+        SimpleProver::<D>::verify(&prover.get_root(), i, j, &nodes, &elements[6]).unwrap();
+
+        let malformed = |bytes: &[u8]| {
+            matches!(
+                parse_canonical_witness::<D, u64>(bytes),
+                Err(ProverError::MalformedWitness)
+            )
+        };
+        assert!(!malformed(&bytes));
+        // a valid proof with indices that don't match its length
+        let shorter = prover.prove_from(30, 29).unwrap();
+        assert_ne!(shorter.len(), witness.len());
+        assert!(malformed(&encode_canonical_witness::<D, u64>(
+            30, 29, &witness
+        )));
+        assert!(malformed(&encode_canonical_witness::<D, u64>(
+            30, 7, &shorter
+        )));
+        // j out of range, and trailing bytes
+        assert!(malformed(&encode_canonical_witness::<D, u64>(
+            30, 31, &witness
+        )));
+        assert!(malformed(&encode_canonical_witness::<D, u64>(
+            30, 0, &witness
+        )));
+        assert!(malformed(&bytes[..bytes.len() - 1]));
+        // an index that doesn't fit the index type
+        let mut wide = bytes.clone();
+        wide[23] = 1;
+        assert!(malformed(&wide));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn emits_metrics_counters() {
+        use metrics::{GaugeValue, Key, Recorder, Unit};
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+
+        thread_local! {
+            static COUNTS: RefCell<HashMap<String, u64>> = RefCell::new(HashMap::new());
+        }
+
+        // Tests run in parallel, so count per thread
+        struct ThreadCounts;
+        impl Recorder for ThreadCounts {
+            fn register_counter(&self, _: &Key, _: Option<Unit>, _: Option<&'static str>) {}
+            fn register_gauge(&self, _: &Key, _: Option<Unit>, _: Option<&'static str>) {}
+            fn register_histogram(&self, _: &Key, _: Option<Unit>, _: Option<&'static str>) {}
+            fn increment_counter(&self, key: &Key, value: u64) {
+                COUNTS.with(|c| *c.borrow_mut().entry(key.name().to_owned()).or_default() += value);
+            }
+            fn update_gauge(&self, _: &Key, _: GaugeValue) {}
+            fn record_histogram(&self, _: &Key, _: f64) {}
+        }
+        let _ = metrics::set_boxed_recorder(Box::new(ThreadCounts));
+        let count = |name: &str| COUNTS.with(|c| c.borrow().get(name).copied().unwrap_or(0));
+
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        for i in 0u8..5 {
+            prover.insert_data([i]);
+        }
+        let witness = prover.prove(2).unwrap();
+        let other = prover.prove(5).unwrap();
+        let element = *prover.get_element(&2.into()).unwrap();
+        let verify = |witness: &[Output<sha2::Sha256>]| {
+            SimpleProver::<sha2::Sha256>::verify(
+                &prover.get_root(),
+                5.into(),
+                2.into(),
+                witness,
+                &element,
+            )
+        };
+        verify(&witness).unwrap();
+        assert!(verify(&other).is_err());
+
+        assert_eq!(count(counters::INSERTS), 5);
+        assert_eq!(count(counters::PROOFS), 2);
+        assert_eq!(
+            count(counters::WITNESS_BYTES),
+            32 * (witness.len() + other.len()) as u64
+        );
+        assert_eq!(count(counters::VERIFY_SUCCESSES), 1);
+        assert_eq!(count(counters::VERIFY_FAILURES), 1);
+    }
+
+    #[test]
+    fn proves_slices() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let proof = prover.prove_slice(40, 10, 14).unwrap();
+        let separate: usize = (10u8..=14).map(|j| prover.prove(j).unwrap().len()).sum();
+        assert!(proof.witness.len() < separate);
+
+        let proven = SimpleProver::<sha2::Sha256>::verify_slice(&root, 40.into(), &proof).unwrap();
+        assert_eq!(proven.len(), 5);
+        for (j, element) in proven {
+            assert!((10..=14).contains(&j.as_u64()));
+            assert_eq!(prover.get_element(&j), Some(&element));
+        }
+
+        let mut bad = proof.clone();
+        let last = bad.witness.len() - 3;
+        bad.witness[last] = Default::default();
+        assert!(SimpleProver::<sha2::Sha256>::verify_slice(&root, 40.into(), &bad).is_err());
+        bad.witness = proof.witness[..proof.witness.len() - 3].to_vec();
+        assert!(matches!(
+            SimpleProver::<sha2::Sha256>::verify_slice(&root, 40.into(), &bad),
+            Err(ProverError::WitnessTooShort)
+        ));
+        bad.witness = proof.witness.clone();
+        bad.witness.push(Default::default());
+        assert!(matches!(
+            SimpleProver::<sha2::Sha256>::verify_slice(&root, 40.into(), &bad),
+            Err(ProverError::WitnessTooLong)
+        ));
+        assert!(prover.prove_slice(40, 14, 10).is_err());
+    }
+
+    #[test]
+    fn verifies_truncated_roots() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let witness = prover.prove(7).unwrap();
+        let element = *prover.get_element(&7.into()).unwrap();
+
+        P::verify_truncated(&root[..16], 20.into(), 7.into(), &witness, &element).unwrap();
+        let mut prefix = root[..16].to_vec();
+        prefix[15] ^= 1;
+        assert!(matches!(
+            P::verify_truncated(&prefix, 20.into(), 7.into(), &witness, &element),
+            Err(ProverError::RiMismatch(0))
+        ));
+        let other = *prover.get_element(&8.into()).unwrap();
+        assert!(P::verify_truncated(&root[..16], 20.into(), 7.into(), &witness, &other).is_err());
+    }
+
+    #[test]
+    fn extends_from_iterators() {
+        let data: Vec<[u8; 1]> = (0u8..20).map(|i| [i]).collect();
+        let mut manual = SimpleProver::<sha2::Sha256>::default();
+        for d in &data {
+            manual.insert_data(d);
+        }
+
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        prover.extend(data[..10].iter().map(|d| &d[..]));
+        prover.extend(data[10..].iter().map(|d| sha2::Sha256::digest(d)));
+        assert_eq!(prover.len(), manual.len());
+        assert_eq!(prover.get_root(), manual.get_root());
+        assert_eq!(prover.elements(), manual.elements());
+        assert_eq!(prover.r(), manual.r());
+        assert_eq!(prover.prove(3).unwrap(), manual.prove(3).unwrap());
+
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        acc.extend(data.iter().map(|d| &d[..]));
+        assert_eq!(acc.get_root(), manual.get_root());
+        assert_eq!(acc.state(), manual.state());
+    }
+
+    #[test]
+    fn proof_walk_matches_prove_from() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..50).map(|i| sha2::Sha256::digest(&[i])).collect();
+        for j in 1u8..=50 {
+            let walked: Result<Vec<_>, _> = prover.proof_walk(50, j).collect();
+            assert_eq!(walked.unwrap(), prover.prove_from(50, j).unwrap());
+        }
+
+        let mut walk = prover.proof_walk(50, 51);
+        assert!(matches!(walk.next(), Some(Err(ProverError::OutOfBounds))));
+        assert!(walk.next().is_none());
+        assert!(prover.proof_walk(50, 0).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn verifies_structure_without_element() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let witness = prover.prove(6).unwrap();
+        let wrong = *prover.get_element(&7.into()).unwrap();
+
+        P::verify_structure(&root, 20.into(), 6.into(), &witness).unwrap();
+        assert!(matches!(
+            P::verify(&root, 20.into(), 6.into(), &witness, &wrong),
+            Err(ProverError::XiMismatch)
+        ));
+
+        let mut bad = witness.clone();
+        bad[1] = Default::default();
+        assert!(P::verify_structure(&root, 20.into(), 6.into(), &bad).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_roots_mid_chain() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let element = *prover.get_element(&3.into()).unwrap();
+        let witness = prover.prove(3).unwrap();
+
+        // 20 skips to pred(20) = 16, whose root is zeroed here
+        let mut bad = witness.clone();
+        bad[2] = P::empty_root();
+        assert!(P::verify(&root, 20.into(), 3.into(), &bad, &element).is_err());
+
+        // A zero root passed in directly is never taken for the sentinel
+        let zero = P::empty_root();
+        assert!(matches!(
+            P::verify(&zero, 20.into(), 3.into(), &witness, &element),
+            Err(ProverError::UnexpectedEmptyRoot)
+        ));
+        let shape = prover.prove(20).unwrap();
+        assert!(matches!(
+            P::verify_structure(&zero, 20.into(), 20.into(), &shape),
+            Err(ProverError::UnexpectedEmptyRoot)
+        ));
+    }
+
+    #[test]
+    fn capacity_is_unobservable() {
+        let mut sized = SimpleProver::<sha2::Sha256>::with_capacity(100);
+        let mut plain = SimpleProver::<sha2::Sha256>::default();
+        assert_eq!(sized.elements(), plain.elements());
+        for i in 0u8..150 {
+            sized.insert_data([i]);
+            plain.insert_data([i]);
+        }
+        assert_eq!(sized.get_root(), plain.get_root());
+        assert_eq!(sized.elements(), plain.elements());
+        assert_eq!(sized.r(), plain.r());
+        assert_eq!(sized.prove(7).unwrap(), plain.prove(7).unwrap());
+    }
+
+    #[test]
+    fn expected_witness_len_matches_proofs() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        for i in 1u64..=40 {
+            for j in 1..=i {
+                let len = prover.prove_from(i, j).unwrap().len();
+                assert_eq!(
+                    expected_witness_len(U256::from(i), U256::from(j)).unwrap(),
+                    len
+                );
+            }
+        }
+        assert!(expected_witness_len(U256::from(3), U256::from(4)).is_err());
+        assert!(expected_witness_len(U256::from(3), U256::zero()).is_err());
+    }
+
+    #[test]
+    fn tags_elements_by_index() {
+        let mut tagged = TaggedProver::<sha2::Sha256, u64>::default();
+        let mut plain = SimpleProver::<sha2::Sha256>::default();
+        for i in 0u8..10 {
+            let element = sha2::Sha256::digest(&[i]);
+            let root = tagged.insert_tagged(&element, 1000 + i as u64);
+            assert_eq!(root, plain.insert(&element));
+        }
+        assert_eq!(tagged.tag_at(1), Some(&1000));
+        assert_eq!(tagged.tag_at(10), Some(&1009));
+        assert_eq!(tagged.tag_at(0), None);
+        assert_eq!(tagged.tag_at(11), None);
+
+        // proofs are unaffected by tags
+        assert_eq!(tagged.prove(4).unwrap(), plain.prove(4).unwrap());
+        let (prover, tags) = tagged.into_parts();
+        assert_eq!(prover.get_root(), plain.get_root());
+        assert_eq!(tags.len(), 10);
+    }
+
+    #[test]
+    fn pins_block_sizes() {
+        type P = SimpleProver<sha2::Sha256, u64>;
+        let sizes: Vec<u64> = (1u64..=16).map(P::block_size).collect();
+        assert_eq!(sizes, [1, 2, 1, 4, 1, 2, 1, 8, 1, 2, 1, 4, 1, 2, 1, 16]);
+        for i in 1u64..=16 {
+            assert_eq!(P::block_start(i) + P::block_size(i), i);
+        }
+        assert_eq!(P::block_start(12u64), 8);
+        assert_eq!(P::block_size(0u64), 0);
+        assert_eq!(P::block_start(0u64), 0);
+    }
+
+    #[cfg(feature = "bench")]
+    #[test]
+    fn bench_elements_are_reproducible() {
+        let a = bench_elements::<sha2::Sha256>(7, 100);
+        assert_eq!(a, bench_elements::<sha2::Sha256>(7, 100));
+        assert_ne!(a, bench_elements::<sha2::Sha256>(8, 100));
+        assert_eq!(a[..10], bench_elements::<sha2::Sha256>(7, 10)[..]);
+        assert_eq!(bench_elements::<sha2::Sha256>(0, 2).len(), 2);
+    }
+
+    #[test]
+    fn empty_root_is_the_root_of_empty() {
+        let acc = SimpleAccumulator::<sha2::Sha256>::default();
+        let prover = SimpleProver::<sha2::Sha256, u64>::default();
+        assert_eq!(
+            acc.get_root(),
+            SimpleAccumulator::<sha2::Sha256>::empty_root()
+        );
+        assert_eq!(
+            prover.get_root(),
+            SimpleProver::<sha2::Sha256, u64>::empty_root()
+        );
+        assert_eq!(prover.root_at(0u64), Some(prover.get_root()));
+        assert!(SimpleProver::<sha2::Sha256, u64>::is_empty_root(
+            &prover.get_root()
+        ));
+    }
+
+    #[test]
+    fn verifies_at_own_root() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let element = *prover.get_element(&5.into()).unwrap();
+        let witness = prover.prove(5).unwrap();
+        prover.verify_at(20, 5, &witness, &element).unwrap();
+
+        // trailing nodes are rejected by verify itself
+        let mut padded = witness.clone();
+        padded.push(Default::default());
+        assert!(matches!(
+            prover.verify_at(20, 5, &padded, &element),
+            Err(ProverError::WitnessTooLong)
+        ));
+        assert!(prover.verify_at(20, 6, &witness, &element).is_err());
+        assert!(matches!(
+            prover.verify_at(21, 5, &witness, &element),
+            Err(ProverError::MissingHistory(_))
+        ));
+    }
+
+    #[test]
+    fn reads_fixed_records() {
+        let data: Vec<u8> = (0u8..=255).cycle().take(40 * 7).collect();
+        let acc = SimpleAccumulator::<sha2::Sha256>::from_reader(&data[..], 7).unwrap();
+        let mut manual = SimpleAccumulator::<sha2::Sha256>::default();
+        for record in data.chunks(7) {
+            manual.insert_data(record);
+        }
+        assert_eq!(acc.len(), 40.into());
+        assert_eq!(acc.get_root(), manual.get_root());
+
+        let err = SimpleAccumulator::<sha2::Sha256>::from_reader(&data[..data.len() - 1], 7);
+        assert_eq!(err.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(SimpleAccumulator::<sha2::Sha256>::from_reader(&data[..], 0).is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn reads_async_records() {
+        let data: Vec<u8> = (0u8..=255).cycle().take(600 * 5).collect();
+        let sync = SimpleAccumulator::<sha2::Sha256>::from_reader(&data[..], 5).unwrap();
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let acc = rt
+            .block_on(SimpleAccumulator::<sha2::Sha256>::from_async_reader(
+                &data[..],
+                5,
+            ))
+            .unwrap();
+        assert_eq!(acc.get_root(), sync.get_root());
+
+        let partial = rt.block_on(SimpleAccumulator::<sha2::Sha256>::from_async_reader(
+            &data[..data.len() - 2],
+            5,
+        ));
+        assert_eq!(
+            partial.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn errors_match_with_wildcard() {
+        // Downstream crates must match `ProverError` with a wildcard arm, as
+        // it is non_exhaustive; this is how they would classify errors
+        fn describe(e: ProverError) -> &'static str {
+            match e {
+                ProverError::WitnessTooShort | ProverError::WitnessTooLong => "length",
+                ProverError::MalformedWitness => "shape",
+                ProverError::RiMismatch(_) | ProverError::XiMismatch => "hash",
+                _ => "other",
+            }
+        }
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..8).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let element = *prover.get_element(&2.into()).unwrap();
+        let witness = prover.prove(2).unwrap();
+        let root = prover.get_root();
+        let short = SimpleProver::<sha2::Sha256>::verify(
+            &root,
+            8.into(),
+            2.into(),
+            &witness[..3],
+            &element,
+        );
+        assert_eq!(describe(short.unwrap_err()), "length");
+        assert_eq!(describe(prover.prove(9).unwrap_err()), "other");
+        assert_eq!(describe(ProverError::MalformedWitness), "shape");
+    }
+
+    #[test]
+    fn diffs_state_slots() {
+        let mut ours = SimpleAccumulator::<sha2::Sha256>::default();
+        for i in 0u8..6 {
+            ours.insert_data([i]);
+        }
+        let mut theirs = ours.clone();
+        assert!(ours.diff(&theirs).is_empty());
+
+        // 7 = 0b111 only writes slot 0
+        theirs.insert_data([6]);
+        let diff = ours.diff(&theirs);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0], (0, ours.get_state(5), Some(theirs.get_root())));
+
+        // 8 = 0b1000 writes slot 3, which `ours` has never had
+        let mut more = theirs.clone();
+        more.insert_data([7]);
+        let diff = theirs.diff(&more);
+        assert_eq!(diff, vec![(3, None, Some(more.get_root()))]);
+        assert_eq!(more.diff(&theirs), vec![(3, Some(more.get_root()), None)]);
+    }
+
+    #[test]
+    fn proves_sparse_sets() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..64).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let js: Vec<U256> = [33u64, 3, 30, 60, 31, 17, 32, 5, 31]
+            .iter()
+            .map(|&j| j.into())
+            .collect();
+
+        let proof = prover.prove_many(64, &js).unwrap();
+        let runs: Vec<(u64, u64)> = proof
+            .runs
+            .iter()
+            .map(|(lo, hi)| (lo.as_u64(), hi.as_u64()))
+            .collect();
+        assert_eq!(runs, [(60, 60), (30, 33), (17, 17), (5, 5), (3, 3)]);
+        let separate: usize = [3u8, 5, 17, 30, 31, 32, 33, 60]
+            .iter()
+            .map(|&j| prover.prove(j).unwrap().len())
+            .sum();
+        assert!(proof.witness.len() < separate);
+
+        let proven = P::verify_sparse(&root, 64.into(), &proof).unwrap();
+        let indices: Vec<u64> = proven.iter().map(|(j, _)| j.as_u64()).collect();
+        assert_eq!(indices, [3, 5, 17, 30, 31, 32, 33, 60]);
+        for (j, element) in &proven {
+            assert_eq!(prover.get_element(j), Some(element));
+        }
+
+        let mut bad = proof.clone();
+        let last = bad.witness.len() - 3;
+        bad.witness[last] = Default::default();
+        assert!(P::verify_sparse(&root, 64.into(), &bad).is_err());
+        let mut bad = proof.clone();
+        bad.runs.swap(0, 1);
+        assert!(P::verify_sparse(&root, 64.into(), &bad).is_err());
+        assert!(prover.prove_many(64, &[0.into()]).is_err());
+    }
+
+    #[test]
+    fn inserts_foreign_elements() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        prover.insert_data("native");
+        // the first 32 bytes of a SHA-512 output stand in for another
+        // digest's elements
+        let foreign = sha2::Sha512::digest(b"foreign");
+        prover.insert_foreign_element(&foreign[..32]).unwrap();
+        prover.insert_data("native again");
+        assert!(prover.insert_foreign_element(&foreign).is_err());
+        assert_eq!(prover.len(), 3.into());
+
+        let element = *prover.get_element(&2.into()).unwrap();
+        assert_eq!(element[..], foreign[..32]);
+        let witness = prover.prove(2).unwrap();
+        SimpleProver::<sha2::Sha256>::verify(
+            &prover.get_root(),
+            3.into(),
+            2.into(),
+            &witness,
+            &element,
+        )
+        .unwrap();
+
+        // re-hashing under the accumulator's digest instead
+        let rehashed = prover.insert_data(foreign);
+        assert_ne!(*prover.get_element(&4.into()).unwrap(), element);
+        assert_eq!(rehashed, prover.get_root());
+    }
+
+    #[test]
+    fn compact_witnesses_verify() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..100).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        for j in [1u64, 37, 64, 99, 100].iter().map(|&j| U256::from(j)) {
+            let witness = prover.prove(j).unwrap();
+            let compact = P::shrink_witness(100.into(), j, &witness).unwrap();
+            let steps = witness.len() / 3;
+            assert_eq!(compact.nodes.len(), 2 * steps + 1);
+            let element = *prover.get_element(&j).unwrap();
+            P::verify_compact(&root, 100.into(), j, &compact, &element).unwrap();
+
+            let mut bad = compact.clone();
+            bad.nodes[0] = Default::default();
+            assert!(P::verify_compact(&root, 100.into(), j, &bad, &element).is_err());
+        }
+
+        let witness = prover.prove(37).unwrap();
+        assert!(matches!(
+            P::shrink_witness(100.into(), 37.into(), &witness[3..]),
+            Err(ProverError::WitnessTooShort)
+        ));
+        let compact = P::shrink_witness(100.into(), 37.into(), &witness).unwrap();
+        assert!(P::verify_compact(&root, 100.into(), 36.into(), &compact, &witness[0]).is_err());
+    }
+
+    #[test]
+    fn dedups_equal_accumulators() {
+        let build = |n: u8| -> SimpleAccumulator<sha2::Sha256> {
+            (0..n).map(|i| sha2::Sha256::digest(&[i])).collect()
+        };
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(build(10)));
+        assert!(!set.insert(build(10)));
+        assert!(set.insert(build(11)));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&build(11)));
+
+        // a prover's history doesn't take part
+        let prover: SimpleProver<sha2::Sha256> =
+            (0..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        assert!(set.contains(&SimpleAccumulator::from(prover)));
+    }
+
+    #[test]
+    fn proves_against_root_value() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        for i in 0u8..12 {
+            prover.insert_data([i]);
+        }
+        let historical = prover.get_root();
+        for i in 12u8..30 {
+            prover.insert_data([i]);
+        }
+
+        let (i, witness) = prover.prove_against_root(&historical, 5).unwrap();
+        assert_eq!(i, 12.into());
+        assert_eq!(witness, prover.prove_from(12, 5).unwrap());
+        let element = *prover.get_element(&5.into()).unwrap();
+        SimpleProver::<sha2::Sha256>::verify(&historical, i, 5.into(), &witness, &element).unwrap();
+
+        assert!(matches!(
+            prover.prove_against_root(&Default::default(), 5),
+            Err(ProverError::UnknownRoot)
+        ));
+        assert!(prover.prove_against_root(&historical, 13).is_err());
+    }
+
+    #[test]
+    fn round_trips_paths() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let element = *prover.get_element(&6.into()).unwrap();
+        let path = prover.proof_as_path(20, 6).unwrap();
+        let witness = prover.prove(6).unwrap();
+        assert_eq!(path.len(), witness.len());
+        assert!(path.iter().map(|(n, _)| n).eq(witness.iter()));
+
+        // one `Next` per step taken, matching the decisions
+        let next: Vec<Branch> = path
+            .iter()
+            .filter_map(|(_, d)| match d {
+                Direction::Next(b) => Some(*b),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(next, P::proof_decisions(20, 6));
+        assert_eq!(path[path.len() - 3].1, Direction::Element);
+        P::verify_path(&root, 20.into(), 6.into(), &path, &element).unwrap();
+
+        let mut bad = path.clone();
+        let next = bad
+            .iter()
+            .position(|(_, d)| matches!(d, Direction::Next(_)))
+            .unwrap();
+        bad[next].1 = Direction::Sibling(Branch::Prev);
+        assert!(matches!(
+            P::verify_path(&root, 20.into(), 6.into(), &bad, &element),
+            Err(ProverError::MalformedWitness)
+        ));
+    }
+
+    #[test]
+    fn set_state_is_guarded() {
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        let root = sha2::Sha256::digest(b"root");
+        assert!(matches!(
+            acc.set_state(0, &root),
+            Err(ProverError::OutOfBounds)
+        ));
+        for i in 0u8..6 {
+            acc.insert_data([i]);
+        }
+        let before = acc.clone();
+        for i in [1u64, 4, 5, 7, 8].iter() {
+            assert!(matches!(
+                acc.set_state(*i, &root),
+                Err(ProverError::OutOfBounds)
+            ));
+        }
+        assert_eq!(acc, before);
+
+        acc.set_state(6, &root).unwrap();
+        assert_eq!(acc.get_root(), root);
+        assert_eq!(acc.len(), 6.into());
+        assert_eq!(acc.get_state(5), before.get_state(5));
+    }
+
+    #[test]
+    fn checks_slot_invariants() {
+        for n in [0u8, 1, 2, 3, 7, 8, 9, 100, 255].iter() {
+            let acc: SimpleAccumulator<sha2::Sha256, u64> =
+                (0..*n).map(|i| sha2::Sha256::digest(&[i])).collect();
+            acc.check_invariants().unwrap();
+        }
+
+        let mut acc: SimpleAccumulator<sha2::Sha256> =
+            (0u8..9).map(|i| sha2::Sha256::digest(&[i])).collect();
+        acc.s.remove(&2);
+        assert!(matches!(
+            acc.check_invariants(),
+            Err(ProverError::InconsistentState(_))
+        ));
+        let mut acc: SimpleAccumulator<sha2::Sha256> =
+            (0u8..9).map(|i| sha2::Sha256::digest(&[i])).collect();
+        acc.s.insert(4, Default::default());
+        assert!(acc.check_invariants().is_err());
+    }
+
+    #[test]
+    fn verifies_with_u64_indices() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..12).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let element = *prover.get_element(&3.into()).unwrap();
+        let witness = prover.prove(3).unwrap();
+        P::verify_u64(&root, 12, 3, &witness, &element).unwrap();
+        assert!(P::verify_u64(&root, 12, 4, &witness, &element).is_err());
+        assert!(P::verify_u64(&root, 3, 12, &witness, &element).is_err());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn proof_json_matches_schema() {
+        use crate::codegen::{proof_json_schema, validate_proof_json};
+
+        let schema = proof_json_schema();
+        assert_eq!(schema["title"], serde_json::json!("ProofBundle"));
+
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let bundle = prover.latest_proof_bundle().unwrap();
+        let value = serde_json::to_value(&bundle).unwrap();
+        validate_proof_json(&value).unwrap();
+
+        let mut bad = value.clone();
+        bad["witness"].as_array_mut().unwrap().pop();
+        assert!(validate_proof_json(&bad).is_err());
+        let mut bad = value.clone();
+        bad["root"] = serde_json::json!("00");
+        assert!(validate_proof_json(&bad).is_err());
+        let mut bad = value.clone();
+        bad["j"] = serde_json::json!("0x15");
+        assert!(validate_proof_json(&bad).is_err());
+        let mut bad = value;
+        bad.as_object_mut().unwrap().remove("element");
+        assert!(validate_proof_json(&bad).is_err());
+    }
+
+    #[test]
+    fn finds_min_provable_index() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..64).map(|i| sha2::Sha256::digest(&[i])).collect();
+        assert_eq!(prover.min_provable_index(64), Some(1.into()));
+        assert_eq!(prover.min_provable_index(65), None);
+
+        prover
+            .retain_for_proofs(50.into(), &[40.into(), 35.into()])
+            .unwrap();
+        let min = prover.min_provable_index(50).unwrap();
+        assert_eq!(min, 35.into());
+        assert!(prover.prove_from(50, min).is_ok());
+        assert!(prover.get_element(&min).is_some());
+        assert!(prover
+            .iter_elements()
+            .filter(|(j, _)| *j < min)
+            .all(|(j, _)| prover.prove_from(50, j).is_err()));
+        // the pruned history can't reach anything from the latest root
+        assert_eq!(prover.min_provable_index(64), None);
+    }
+
+    #[test]
+    fn strict_and_lenient_trailing_nodes() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let element = *prover.get_element(&9.into()).unwrap();
+        let witness = prover.prove(9).unwrap();
+        P::verify(&root, 20.into(), 9.into(), &witness, &element).unwrap();
+        P::verify_lenient(&root, 20.into(), 9.into(), &witness, &element).unwrap();
+
+        let mut padded = witness.clone();
+        padded.extend(vec![
+            Output::<sha2::Sha256>::default();
+            LENIENT_TRAILING_NODES
+        ]);
+        assert!(matches!(
+            P::verify(&root, 20.into(), 9.into(), &padded, &element),
+            Err(ProverError::WitnessTooLong)
+        ));
+        P::verify_lenient(&root, 20.into(), 9.into(), &padded, &element).unwrap();
+        let mut job =
+            VerifyJob::<sha2::Sha256>::new(&root, 20.into(), 9.into(), &padded, &element, 100);
+        assert!(matches!(job.step(), Some(Err(ProverError::WitnessTooLong))));
+
+        padded.push(Default::default());
+        assert!(matches!(
+            P::verify_lenient(&root, 20.into(), 9.into(), &padded, &element),
+            Err(ProverError::WitnessTooLong)
+        ));
+    }
+
+    #[test]
+    fn exported_package_verifies() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let package = prover.export_for_verifier();
+        assert_eq!(package.size, 20.into());
+        assert_eq!(package.digest, "sha256");
+        for j in [1u8, 7, 16, 20] {
+            let element = *prover.get_element(&j.into()).unwrap();
+            let witness = prover.prove(j).unwrap();
+            package.verify(&witness, j.into(), &element).unwrap();
+        }
+
+        // a proof against a later size no longer matches the pinned root
+        prover.insert(&sha2::Sha256::digest(b"later"));
+        let element = *prover.get_element(&7.into()).unwrap();
+        let witness = prover.prove(7).unwrap();
+        assert!(package.verify(&witness, 7.into(), &element).is_err());
+    }
+
+    #[test]
+    fn proves_with_wide_outputs() {
+        type P = SimpleProver<sha2::Sha512>;
+        let mut prover: P = (0u8..20).map(|i| sha2::Sha512::digest(&[i])).collect();
+        let root = prover.get_root();
+        assert_eq!(root.len(), 64);
+        for j in 1u8..=20 {
+            let element = *prover.get_element(&j.into()).unwrap();
+            let witness = prover.prove(j).unwrap();
+            P::verify(&root, 20.into(), j.into(), &witness, &element).unwrap();
+        }
+        assert!(matches!(
+            prover.insert_bytes(&[0u8; 32]),
+            Err(ProverError::InvalidElementLength(32))
+        ));
+        prover.insert_bytes(&[7u8; 64]).unwrap();
+        assert_eq!(prover.len(), 21.into());
+    }
+
+    #[cfg(feature = "bloom")]
+    #[test]
+    fn bloom_has_no_false_negatives() {
+        let prover: SimpleProver<sha2::Sha256> = (0u32..1000)
+            .map(|i| sha2::Sha256::digest(&i.to_be_bytes()))
+            .collect();
+        for (_, x) in prover.iter_elements() {
+            assert!(prover.might_contain(x));
+        }
+        let false_positives = (1000u32..11000)
+            .filter(|i| prover.might_contain(&sha2::Sha256::digest(&i.to_be_bytes())))
+            .count();
+        assert!(false_positives < 10, "{} false positives", false_positives);
+
+        // from_parts rebuilds the filter from the elements it is given
+        let mut restored = SimpleProver::<sha2::Sha256>::from_parts(
+            prover.len(),
+            prover.state().clone(),
+            prover.elements().clone(),
+            prover.r().clone(),
+        )
+        .unwrap();
+        assert_eq!(restored.bloom(), prover.bloom());
+        restored.restore_bloom(Bloom::default());
+        assert!(!restored.might_contain(&sha2::Sha256::digest(&5u32.to_be_bytes())));
+        let stored = prover.bloom().to_bytes();
+        restored.restore_bloom(Bloom::from_bytes(&stored).unwrap());
+        assert!(restored.might_contain(&sha2::Sha256::digest(&5u32.to_be_bytes())));
+        assert!(Bloom::from_bytes(&stored[..stored.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn verifies_historical_root() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let r_6 = *prover.get_r(&6.into()).unwrap();
+        let witness = prover.prove(6).unwrap();
+        P::verify_root_at(&root, 20.into(), 6.into(), &witness, &r_6).unwrap();
+        P::verify_root_at(
+            &root,
+            20.into(),
+            20.into(),
+            &prover.prove(20).unwrap(),
+            &root,
+        )
+        .unwrap();
+
+        let r_7 = *prover.get_r(&7.into()).unwrap();
+        let last = utils::proof_steps(U256::from(20), U256::from(6)) - 1;
+        assert!(matches!(
+            P::verify_root_at(&root, 20.into(), 6.into(), &witness, &r_7),
+            Err(ProverError::RiMismatch(n)) if n == last
+        ));
+    }
+
+    #[test]
+    fn iterates_by_reference() {
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let mut seen = vec![];
+        for (i, e) in &prover {
+            seen.push((i, *e));
+        }
+        assert_eq!(seen.len(), 20);
+        let iterated: Vec<_> = prover.iter_elements().map(|(i, x)| (i, *x)).collect();
+        assert_eq!(seen, iterated);
+    }
+
+    #[test]
+    fn proves_from_published_root() {
+        type P = SimpleProver<sha2::Sha256>;
+        let mut prover: P = (0u8..12).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let published = prover.get_root();
+        prover.extend((12u8..30).map(|i| sha2::Sha256::digest(&[i])));
+
+        let element = *prover.get_element(&5.into()).unwrap();
+        let witness = prover.prove_from_root(&published, 5).unwrap();
+        assert_eq!(witness, prover.prove_from(12, 5).unwrap());
+        P::verify(&published, 12.into(), 5.into(), &witness, &element).unwrap();
+        assert!(matches!(
+            prover.prove_from_root(&published, 13),
+            Err(ProverError::OutOfBounds)
+        ));
+        assert!(matches!(
+            prover.prove_from_root(&Default::default(), 5),
+            Err(ProverError::UnknownRoot)
+        ));
+    }
+
+    #[test]
+    fn caps_proof_len() {
+        type P = SimpleProver<sha2::Sha256>;
+        let mut prover: P = (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let (root, element) = (prover.get_root(), *prover.get_element(&3.into()).unwrap());
+        let witness = prover.prove(3).unwrap();
+        let len = witness.len();
+
+        prover.set_max_proof_len(Some(len));
+        assert_eq!(prover.prove(3).unwrap(), witness);
+        P::verify_capped(&root, 40.into(), 3.into(), &witness, &element, len).unwrap();
+
+        prover.set_max_proof_len(Some(len - 1));
+        assert!(matches!(
+            prover.prove(3),
+            Err(ProverError::ProofTooLarge(l)) if l == len
+        ));
+        // short proofs are still served
+        prover.prove(40).unwrap();
+        assert!(matches!(
+            P::verify_capped(&root, 40.into(), 3.into(), &witness, &element, len - 1),
+            Err(ProverError::ProofTooLarge(l)) if l == len
+        ));
+    }
+
+    #[test]
+    fn genesis_separates_logs() {
+        type P = SimpleProver<sha2::Sha256>;
+        let genesis = sha2::Sha256::digest(b"deployment a");
+        let mut ours = P::with_genesis(genesis);
+        assert_eq!(ours.get_root(), genesis);
+        assert_eq!(ours.get_r(&0.into()), Some(&genesis));
+        let mut theirs = P::with_genesis(sha2::Sha256::digest(b"deployment b"));
+        let mut plain = P::default();
+        for i in 0u8..20 {
+            let x = sha2::Sha256::digest(&[i]);
+            ours.insert(&x);
+            theirs.insert(&x);
+            plain.insert(&x);
+        }
+        assert_ne!(ours.get_root(), theirs.get_root());
+        assert_ne!(ours.get_root(), plain.get_root());
+        let acc: SimpleAccumulator<sha2::Sha256> = SimpleAccumulator::with_genesis(genesis);
+        let acc = (0u8..20).fold(acc, |mut acc, i| {
+            acc.insert(&sha2::Sha256::digest(&[i]));
+            acc
+        });
+        assert_eq!(acc.get_root(), ours.get_root());
+
+        for j in [1u8, 8, 16, 20] {
+            let element = *ours.get_element(&j.into()).unwrap();
+            let witness = ours.prove(j).unwrap();
+            P::verify(&ours.get_root(), 20.into(), j.into(), &witness, &element).unwrap();
+            assert!(
+                P::verify(&theirs.get_root(), 20.into(), j.into(), &witness, &element).is_err()
+            );
+            assert!(P::verify(&plain.get_root(), 20.into(), j.into(), &witness, &element).is_err());
+        }
+
+        // the genesis, not the all-zero root, is what no later root may be
+        let root = ours.get_root();
+        let element = *ours.get_element(&3.into()).unwrap();
+        let witness = ours.prove(3).unwrap();
+        P::verify_with_genesis(&genesis, &root, 20.into(), 3.into(), &witness, &element).unwrap();
+        ours.verify_at(20, 3, &witness, &element).unwrap();
+        assert!(matches!(
+            P::verify_with_genesis(&genesis, &genesis, 20.into(), 3.into(), &witness, &element),
+            Err(ProverError::UnexpectedEmptyRoot)
+        ));
+        assert!(matches!(
+            P::verify(&genesis, 20.into(), 3.into(), &witness, &element),
+            Err(ProverError::RiMismatch(0))
+        ));
+        assert!(matches!(
+            P::verify_with_genesis(
+                &genesis,
+                &P::empty_root(),
+                20.into(),
+                3.into(),
+                &witness,
+                &element
+            ),
+            Err(ProverError::RiMismatch(0))
+        ));
+        let mut job =
+            VerifyJob::<sha2::Sha256>::new(&genesis, 20.into(), 3.into(), &witness, &element, 1)
+                .with_genesis(&genesis);
+        let result = loop {
+            if let Some(result) = job.step() {
+                break result;
+            }
+        };
+        assert!(matches!(result, Err(ProverError::UnexpectedEmptyRoot)));
+
+        // and exported proofs carry it
+        let bundle = ours.latest_proof_bundle().unwrap();
+        assert_eq!(bundle.genesis, genesis);
+        bundle.verify().unwrap();
+        let package = ours.export_for_verifier();
+        assert_eq!(package.genesis, genesis);
+        package.verify(&witness, 3.into(), &element).unwrap();
+
+        let restored = P::from_parts(
+            ours.len(),
+            ours.state().clone(),
+            ours.elements().clone(),
+            ours.r().clone(),
+        )
+        .unwrap();
+        assert_eq!(restored.accumulator, ours.accumulator);
+    }
+
+    #[test]
+    fn counts_verification_hashes() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        for j in 1u8..=40 {
+            let element = *prover.get_element(&j.into()).unwrap();
+            let witness = prover.prove(j).unwrap();
+            let hashes = P::verify_counted(&root, 40.into(), j.into(), &witness, &element).unwrap();
+            assert_eq!(hashes, utils::proof_steps(U256::from(40), U256::from(j)));
+        }
+        let witness = prover.prove(3).unwrap();
+        assert!(P::verify_counted(&root, 40.into(), 3.into(), &witness, &root).is_err());
+    }
+
+    #[test]
+    fn interns_repeated_values() {
+        let prover: SimpleProver<sha2::Sha256> = (0u32..300)
+            .map(|i| sha2::Sha256::digest(&[(i % 3) as u8]))
+            .collect();
+        let elements = Interned::from_store(prover.elements());
+        // the three repeated elements and the sentinel
+        assert_eq!(elements.unique_len(), 4);
+        assert_eq!(HistoryStore::len(&elements), 301);
+        assert!(elements.entries().eq(prover.elements().entries()));
+        for j in [1u32, 150, 300] {
+            let j = U256::from(j);
+            assert_eq!(elements.get(&j), prover.get_element(&j));
+        }
+
+        let r = prover.compact_r_map();
+        assert_eq!(r.unique_len(), 301);
+        assert!(r.entries().eq(prover.r().entries()));
+        let mid = U256::from(150);
+        assert_eq!(
+            r.last_at_or_before(&mid),
+            prover.r().last_at_or_before(&mid)
+        );
+    }
+
+    #[test]
+    fn add_assign_inserts() {
+        let mut explicit = SimpleProver::<sha2::Sha256>::default();
+        explicit.insert_data(b"a");
+        explicit.insert(&sha2::Sha256::digest(b"b"));
+        explicit.insert_data(b"c");
+
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        let mut acc = SimpleAccumulator::<sha2::Sha256>::default();
+        prover += &b"a"[..];
+        prover += sha2::Sha256::digest(b"b");
+        prover += &b"c"[..];
+        acc += &b"a"[..];
+        acc += sha2::Sha256::digest(b"b");
+        acc += &b"c"[..];
+        assert_eq!(prover.get_root(), explicit.get_root());
+        assert_eq!(acc.get_root(), explicit.get_root());
+        assert_eq!(prover.prove(2).unwrap(), explicit.prove(2).unwrap());
+    }
+
+    #[test]
+    fn chain_indices_match_witness() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        for j in 1u8..=40 {
+            let indices = P::proof_chain_indices(40, j).unwrap();
+            let witness = prover.prove(j).unwrap();
+            assert_eq!(indices.len(), witness.len());
+            for (n, (index, node)) in indices.iter().zip(&witness).enumerate() {
+                let expected = if n % 3 == 0 {
+                    prover.get_element(index)
+                } else {
+                    prover.get_r(index)
+                };
+                assert_eq!(expected, Some(node));
+            }
+        }
+        assert!(matches!(
+            P::proof_chain_indices(4, 5),
+            Err(ProverError::OutOfBounds)
+        ));
+        assert!(matches!(
+            P::proof_chain_indices(4, 0),
+            Err(ProverError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn verifies_with_runtime_digest() {
+        fn check<D: Digest + Clone + Default>(kind: DigestKind) {
+            let prover: SimpleProver<D> = (0u8..20).map(|i| D::digest(&[i])).collect();
+            let element = prover.get_element(&7.into()).unwrap().clone();
+            let witness = prover.prove(7).unwrap().concat();
+            let root = prover.get_root();
+            assert_eq!(kind.output_size(), root.len());
+            verify_dyn(kind, &root, 20.into(), 7.into(), &witness, &element).unwrap();
+            assert!(verify_dyn(kind, &root, 20.into(), 8.into(), &witness, &element).is_err());
+            assert!(matches!(
+                verify_dyn(kind, &root, 20.into(), 7.into(), &witness[1..], &element),
+                Err(ProverError::MalformedWitness)
+            ));
+        }
+        check::<sha2::Sha256>(DigestKind::Sha256);
+        check::<sha2::Sha512>(DigestKind::Sha512);
+
+        // the same bytes don't verify under another digest of the same size
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..20).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let element = *prover.get_element(&7.into()).unwrap();
+        let witness = prover.prove(7).unwrap().concat();
+        let root = prover.get_root();
+        let kind = DigestKind::Sha512Trunc256;
+        assert!(verify_dyn(kind, &root, 20.into(), 7.into(), &witness, &element).is_err());
+        assert!(matches!(
+            verify_dyn(
+                DigestKind::Sha512,
+                &root,
+                20.into(),
+                7.into(),
+                &witness,
+                &element
+            ),
+            Err(ProverError::InvalidElementLength(32))
+        ));
+    }
+
+    #[test]
+    fn proves_through_two_levels() {
+        type P = SimpleProver<sha2::Sha256>;
+        let subs: Vec<P> = (0u8..3)
+            .map(|s| (0u8..10).map(|i| sha2::Sha256::digest(&[s, i])).collect())
+            .collect();
+        let mut log = P::default();
+        for sub in &subs {
+            log.insert_root_of(sub);
+        }
+        log.insert_root_of(&SimpleAccumulator::<sha2::Sha256>::default());
+        assert_eq!(log.get_element(&4.into()), Some(&P::empty_root()));
+
+        // the leaf at 6 of the second sub-log, which is the leaf at 2 here
+        let (sub, m) = (&subs[1], 2);
+        let leaf = *sub.get_element(&6.into()).unwrap();
+        let inner = sub.prove_from(10, 6).unwrap();
+        let outer = log.prove(m).unwrap();
+        let sub_root = sub.get_root();
+        P::verify(&log.get_root(), 4.into(), m.into(), &outer, &sub_root).unwrap();
+        P::verify(&sub_root, 10.into(), 6.into(), &inner, &leaf).unwrap();
+        assert!(P::verify(&subs[0].get_root(), 10.into(), 6.into(), &inner, &leaf).is_err());
+    }
+
+    #[test]
+    fn parsers_reject_truncated_and_oversized() {
+        type D = sha2::Sha256;
+        let prover: SimpleProver<D> = (0u8..30).map(|i| D::digest(&[i])).collect();
+        let bytes =
+            encode_canonical_witness::<D, U256>(30.into(), 7.into(), &prover.prove(7).unwrap());
+        parse_canonical_witness::<D, U256>(&bytes).unwrap();
+        for len in 0..bytes.len() {
+            assert!(parse_canonical_witness::<D, U256>(&bytes[..len]).is_err());
+        }
+        let mut oversized = bytes.clone();
+        oversized.extend(vec![0u8; 32 * 3 * 1000]);
+        assert!(parse_canonical_witness::<D, U256>(&oversized).is_err());
+        assert!(parse_canonical_witness::<D, U256>(&[0xff; 64]).is_err());
+
+        #[cfg(feature = "bloom")]
+        {
+            let bloom = Bloom::default().to_bytes();
+            Bloom::from_bytes(&bloom).unwrap();
+            for len in [0, 4, 11, 13, bloom.len() - 1] {
+                assert!(Bloom::from_bytes(&bloom[..len]).is_err());
+            }
+            let mut slow = bloom.clone();
+            slow[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+            assert!(Bloom::from_bytes(&slow).is_err());
+        }
+    }
+
+    #[test]
+    fn signs_every_root() {
+        /// Signs by hashing the root with a key
+        struct Signer(Vec<U256>);
+        impl RootSink<sha2::Sha256> for Signer {
+            type Signature = Output<sha2::Sha256>;
+            fn on_root(&mut self, index: U256, root: &Output<sha2::Sha256>) -> Self::Signature {
+                self.0.push(index);
+                sha2::Sha256::new().chain(b"key").chain(root).finalize()
+            }
+        }
+
+        let mut prover = SigningProver::<sha2::Sha256, _>::new(Signer(vec![]));
+        for i in 0u8..10 {
+            prover.insert(&sha2::Sha256::digest(&[i]));
+        }
+        assert_eq!(prover.len(), 10.into());
+        assert!(prover.signature_at(0).is_none());
+        for i in 1u8..=10 {
+            let root = prover.get_r(&i.into()).unwrap();
+            let expected = sha2::Sha256::new().chain(b"key").chain(root).finalize();
+            assert_eq!(prover.signature_at(i), Some(&expected));
+        }
+        let (_, signer, signatures) = prover.into_parts();
+        assert_eq!(signer.0, (1u8..=10).map(U256::from).collect::<Vec<_>>());
+        assert_eq!(signatures.len(), 10);
+    }
+
+    #[test]
+    fn verifies_columns() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..40).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let columns = |witness: &[Output<sha2::Sha256>]| {
+            let column = |c: usize| {
+                witness
+                    .iter()
+                    .skip(c)
+                    .step_by(3)
+                    .copied()
+                    .collect::<Vec<_>>()
+            };
+            (column(0), column(1), column(2))
+        };
+        for j in 1u8..=40 {
+            let element = *prover.get_element(&j.into()).unwrap();
+            let witness = prover.prove(j).unwrap();
+            let (x, prev, pred) = columns(&witness);
+            P::verify_columns(&root, 40.into(), j.into(), &x, &prev, &pred, &element).unwrap();
+        }
+
+        // the same decision as verify on tampered and truncated witnesses
+        let element = *prover.get_element(&5.into()).unwrap();
+        let witness = prover.prove(5).unwrap();
+        let mut bad = witness.clone();
+        bad[4] = sha2::Sha256::digest(b"bad");
+        for w in [bad, witness[..witness.len() - 3].to_vec(), witness.clone()] {
+            let (x, prev, pred) = columns(&w);
+            let flat = P::verify(&root, 40.into(), 5.into(), &w, &element);
+            let cols = P::verify_columns(&root, 40.into(), 5.into(), &x, &prev, &pred, &element);
+            assert_eq!(format!("{:?}", flat), format!("{:?}", cols));
+        }
+        let (x, prev, pred) = columns(&witness);
+        assert!(matches!(
+            P::verify_columns(&root, 40.into(), 5.into(), &x, &prev[1..], &pred, &element),
+            Err(ProverError::MalformedWitness)
+        ));
+        assert!(matches!(
+            P::verify_columns(&root, 40.into(), 5.into(), &x, &prev, &pred, &root),
+            Err(ProverError::XiMismatch)
+        ));
+    }
+
+    #[test]
+    fn truncates_to_earlier_size() {
+        type P = SimpleProver<sha2::Sha256>;
+        let elements: Vec<_> = (0u8..50).map(|i| sha2::Sha256::digest(&[i])).collect();
+        for n in [0usize, 1, 16, 20, 31, 32, 49, 50] {
+            let mut prover: P = elements.iter().copied().collect();
+            let root_n = prover.root_at(n).unwrap();
+            prover.truncate(n).unwrap();
+            let fresh: P = elements[..n].iter().copied().collect();
+            assert_eq!(prover.get_root(), root_n);
+            assert_eq!(prover.len(), U256::from(n));
+            assert_eq!(prover.accumulator, fresh.accumulator);
+            assert!(prover.r().entries().eq(fresh.r().entries()));
+            for j in 1..=n {
+                assert_eq!(prover.prove(j).unwrap(), fresh.prove(j).unwrap());
+            }
+            // and the log carries on as if it had never been longer
+            let mut fresh = fresh;
+            prover.insert(&elements[0]);
+            fresh.insert(&elements[0]);
+            assert_eq!(prover.get_root(), fresh.get_root());
+        }
+
+        let mut prover: P = elements.iter().copied().collect();
+        assert!(matches!(prover.truncate(51), Err(ProverError::OutOfBounds)));
+        prover.retain_for_proofs(50.into(), &[50.into()]).unwrap();
+        assert!(matches!(
+            prover.truncate(20),
+            Err(ProverError::MissingHistory(_))
+        ));
+        assert_eq!(prover.len(), 50.into());
+    }
+
+    #[test]
+    fn validates_lengths_per_output_size() {
+        fn check<D: Digest + Clone + Default>(size: usize) {
+            type P<D> = SimpleProver<D>;
+            assert_eq!(P::<D>::OUTPUT_SIZE, size);
+            let mut prover: P<D> = (0u8..20).map(|i| D::digest(&[i])).collect();
+            for len in [0, 32, 64, size - 1, size + 1] {
+                if len != size {
+                    assert!(matches!(
+                        prover.insert_bytes(&vec![1; len]),
+                        Err(ProverError::InvalidElementLength(l)) if l == len
+                    ));
+                }
+            }
+            prover.insert_bytes(&vec![1; size]).unwrap();
+
+            let witness = prover.prove(7).unwrap();
+            let bytes = encode_canonical_witness::<D, U256>(21.into(), 7.into(), &witness);
+            assert_eq!(bytes.len(), 64 + witness.len() * size);
+            parse_canonical_witness::<D, U256>(&bytes).unwrap();
+            assert!(parse_canonical_witness::<D, U256>(&bytes[..bytes.len() - 1]).is_err());
+
+            #[cfg(feature = "serialize")]
+            {
+                let value = serde_json::to_value(&prover.latest_proof_bundle().unwrap()).unwrap();
+                codegen::validate_proof_json_for(&value, size).unwrap();
+                let other = if size == 32 { 64 } else { 32 };
+                assert!(codegen::validate_proof_json_for(&value, other).is_err());
+            }
+        }
+        check::<sha2::Sha256>(32);
+        check::<sha2::Sha512>(64);
+    }
+
+    #[test]
+    fn proves_with_element() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..30).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let r_25 = prover.root_at(25).unwrap();
+        for j in 1u8..=25 {
+            let (element, witness) = prover.prove_with_element(25, j).unwrap();
+            assert_eq!(Some(&element), prover.get_element(&j.into()));
+            P::verify(&r_25, 25.into(), j.into(), &witness, &element).unwrap();
+        }
+        assert!(matches!(
+            prover.prove_with_element(25, 26),
+            Err(ProverError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn verifies_items_sharing_a_root() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..64).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let i = prover.len();
+        let mut items: Vec<_> = (40u8..=60)
+            .map(|j| {
+                let j = U256::from(j);
+                (
+                    j,
+                    *prover.get_element(&j).unwrap(),
+                    prover.prove(j).unwrap(),
+                )
+            })
+            .collect();
+        // a wrong element, and a witness tampered with at the top, where
+        // it shares a triple with the others
+        items[3].1 = root;
+        items[7].2[0][0] ^= 1;
+        items.push((65.into(), root, vec![]));
+
+        let expected: Vec<_> = items
+            .iter()
+            .map(|(j, element, witness)| P::verify(&root, i, *j, witness, element))
+            .collect();
+        assert!(expected[3].is_err() && expected[7].is_err() && expected[21].is_err());
+        let expected = format!("{:?}", expected);
+        let shared = P::verify_shared_root(&root, i, &items);
+        assert_eq!(format!("{:?}", shared), expected);
+
+        let (results, hashes) =
+            verify::walk_shared::<sha2::Sha256, _>(&root, &Default::default(), i, &items);
+        assert_eq!(format!("{:?}", results), expected);
+        let separately: usize = items
+            .iter()
+            .filter(|(j, _, _)| *j <= i)
+            .map(|(j, _, _)| utils::proof_steps(i, *j))
+            .sum();
+        assert!(hashes < separately / 2, "{} of {}", hashes, separately);
+    }
+
+    #[test]
+    fn reports_count_and_root_from_elements() {
+        let elements: Vec<_> = (0u8..37).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let (acc, len, root) =
+            SimpleAccumulator::<sha2::Sha256>::from_elements_reporting(elements.iter().cloned());
+        assert_eq!(len, U256::from(elements.len()));
+        assert_eq!(root, acc.get_root());
+        assert_eq!(acc, SimpleAccumulator::from_elements(elements.into_iter()));
+
+        let (_, len, root) =
+            SimpleAccumulator::<sha2::Sha256>::from_elements_reporting(std::iter::empty());
+        assert!(len.is_zero());
+        assert!(SimpleAccumulator::<sha2::Sha256>::is_empty_root(&root));
+    }
+
+    #[test]
+    fn proves_consistency_between_checkpoints() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..64).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let checkpoints = [1u8, 2, 5, 8, 13, 16, 31, 32, 40, 63, 64];
+        for pair in checkpoints.windows(2) {
+            let (a, b) = (U256::from(pair[0]), U256::from(pair[1]));
+            let (r_a, r_b) = (prover.root_at(a).unwrap(), prover.root_at(b).unwrap());
+            let witness = prover.prove_consistency_minimal(a, b).unwrap();
+            assert_eq!(witness.len(), 3 * (utils::proof_steps(b, a) - 1));
+            assert!(witness.len() < prover.prove_from(b, a).unwrap().len());
+            P::verify_consistency(&r_b, b, a, &witness, &r_a).unwrap();
+
+            let r_other = prover.root_at(a - 1).unwrap();
+            assert!(matches!(
+                P::verify_consistency(&r_b, b, a, &witness, &r_other),
+                Err(ProverError::RiMismatch(_))
+            ));
+            let mut longer = witness.clone();
+            longer.push(r_a);
+            assert!(P::verify_consistency(&r_b, b, a, &longer, &r_a).is_err());
+        }
+
+        let r = prover.root_at(40).unwrap();
+        let witness = prover.prove_consistency_minimal(40, 40).unwrap();
+        assert!(witness.is_empty());
+        P::verify_consistency(&r, 40.into(), 40.into(), &witness, &r).unwrap();
+        assert!(prover.prove_consistency_minimal(41, 40).is_err());
+        assert!(prover.prove_consistency_minimal(0, 40).is_err());
+        assert!(prover.prove_consistency_minimal(0, 0).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_parts() {
+        type P = SimpleProver<sha2::Sha256>;
+        let genesis = sha2::Sha256::digest(b"genesis");
+        let mut prover = P::with_genesis(genesis);
+        prover.insert_iter((0u8..45).map(|i| sha2::Sha256::digest(&[i])));
+        let (elements, r) = (prover.elements().clone(), prover.r().clone());
+        let root = prover.get_root();
+
+        let (k, s, moved_elements, moved_r) = prover.into_parts();
+        assert_eq!(k, U256::from(45));
+        assert_eq!((&moved_elements, &moved_r), (&elements, &r));
+        let rebuilt = P::from_parts(k, s, moved_elements, moved_r).unwrap();
+        assert_eq!(rebuilt.get_root(), root);
+        assert_eq!(rebuilt.get_state(0), Some(genesis));
+        let witness = rebuilt.prove(17).unwrap();
+        let element = *rebuilt.get_element(&17.into()).unwrap();
+        P::verify(&root, k, 17.into(), &witness, &element).unwrap();
+
+        let (k, s, elements, r) = P::default().into_parts();
+        assert!(k.is_zero() && s.is_empty());
+        assert!(P::from_parts(k, s, elements, r).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_witness() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..5).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let (r_5, x_3) = (prover.get_root(), *prover.get_element(&3.into()).unwrap());
+        for (i, j) in [(5u8, 3u8), (5, 5), (3, 3)] {
+            assert!(matches!(
+                P::verify(&r_5, i.into(), j.into(), &[], &x_3),
+                Err(ProverError::WitnessTooShort)
+            ));
+        }
+    }
+
+    #[test]
+    fn follower_catches_up_from_delta() {
+        type P = SimpleProver<sha2::Sha256>;
+        let all: Vec<_> = (0u8..70).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let leader: P = all.iter().copied().collect();
+        for their_len in [0usize, 1, 23, 64, 69, 70] {
+            let mut follower: P = all[..their_len].iter().copied().collect();
+            let delta = leader.diff_since(their_len).unwrap();
+            assert_eq!(delta.elements.len(), 70 - their_len);
+            assert_eq!(delta.apply(&mut follower).unwrap(), leader.get_root());
+            assert_eq!(follower.get_root(), leader.get_root());
+            assert_eq!(follower.len(), leader.len());
+        }
+
+        let rejected = |delta: &SyncDelta<sha2::Sha256>, follower: &mut P| {
+            let before = follower.clone();
+            let result = delta.apply(follower);
+            assert_eq!(follower.accumulator, before.accumulator);
+            assert_eq!(follower.elements(), before.elements());
+            assert_eq!(follower.r(), before.r());
+            result
+        };
+        for their_len in [0usize, 23] {
+            let mut follower: P = all[..their_len].iter().copied().collect();
+            let honest = leader.diff_since(their_len).unwrap();
+
+            let mut tampered = honest.clone();
+            tampered.elements[5] = sha2::Sha256::digest(b"made up");
+            assert!(matches!(
+                rejected(&tampered, &mut follower),
+                Err(ProverError::InconsistentState(_))
+            ));
+            let mut short = honest.clone();
+            short.elements.pop();
+            assert!(matches!(
+                rejected(&short, &mut follower),
+                Err(ProverError::InconsistentState(_))
+            ));
+            let mut follower_ahead = follower.clone();
+            follower_ahead.insert(&all[their_len]);
+            assert!(rejected(&honest, &mut follower_ahead).is_err());
+        }
+
+        let mut forked: P = (0u8..23).map(|i| sha2::Sha256::digest(&[i, i])).collect();
+        let delta = leader.diff_since(23).unwrap();
+        assert!(matches!(
+            rejected(&delta, &mut forked),
+            Err(ProverError::RiMismatch(_))
+        ));
+        assert!(matches!(
+            leader.diff_since(71),
+            Err(ProverError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn inserts_only_absent_elements() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..10).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let before = prover.clone();
+        let duplicate = sha2::Sha256::digest(&[4]);
+        assert_eq!(
+            prover.insert_if_absent(&duplicate),
+            (false, before.get_root())
+        );
+        assert_eq!(prover.accumulator, before.accumulator);
+        assert_eq!(prover.elements(), before.elements());
+        assert_eq!(prover.r(), before.r());
+
+        let new = sha2::Sha256::digest(&[10]);
+        let (inserted, root) = prover.insert_if_absent(&new);
+        assert!(inserted);
+        assert_eq!(root, prover.get_root());
+        assert_eq!(prover.len(), U256::from(11));
+        assert_eq!(prover.insert_if_absent(&new), (false, root));
+
+        // truncated elements no longer count
+        prover.truncate(U256::from(10)).unwrap();
+        assert!(prover.insert_if_absent(&new).0);
+
+        // neither do amended ones
+        let amended = sha2::Sha256::digest(&[11]);
+        prover.amend_last(&amended).unwrap();
+        assert!(prover.insert_if_absent(&new).0);
+        assert!(!prover.insert_if_absent(&amended).0);
+
+        // nor ones a rolled back transaction inserted
+        let rolled_back = sha2::Sha256::digest(&[12]);
+        {
+            let mut txn = prover.begin();
+            txn.insert(&rolled_back);
+        }
+        assert!(prover.insert_if_absent(&rolled_back).0);
+
+        // nor pruned ones, though duplicates still held elsewhere do
+        let mut pruned: SimpleProver<sha2::Sha256> = (0u8..10)
+            .chain(Some(4))
+            .map(|i| sha2::Sha256::digest(&[i]))
+            .collect();
+        pruned
+            .retain_for_proofs(U256::from(11), &[U256::from(11)])
+            .unwrap();
+        assert!(!pruned.insert_if_absent(&duplicate).0);
+        let dropped = (1u8..10)
+            .find(|i| pruned.elements().get(&U256::from(*i + 1)).is_none())
+            .unwrap();
+        assert!(pruned.insert_if_absent(&sha2::Sha256::digest(&[dropped])).0);
+
+        // the sentinel is not an inserted element
+        let mut empty = SimpleProver::<sha2::Sha256>::default();
+        assert!(empty.insert_if_absent(&Default::default()).0);
+    }
+
+    #[cfg(feature = "capi")]
+    #[test]
+    fn verifies_through_c_abi() {
+        use crate::capi::*;
+
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..30).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let root = prover.get_root();
+        let element = *prover.get_element(&11.into()).unwrap();
+        let witness: Vec<u8> = prover.prove(11).unwrap().concat();
+        let verify = |root: &[u8], i: (u64, u64), j: u64, witness: &[u8], element: &[u8]| unsafe {
+            acc_verify_sha256(
+                root.as_ptr(),
+                i.0,
+                i.1,
+                j,
+                0,
+                witness.as_ptr(),
+                witness.len(),
+                element.as_ptr(),
+            )
+        };
+
+        assert_eq!(verify(&root, (30, 0), 11, &witness, &element), ACC_OK);
+        assert_eq!(
+            verify(&root, (30, 0), 11, &witness, &root),
+            ACC_ERR_ELEMENT_MISMATCH
+        );
+        assert_eq!(
+            verify(&root, (30, 0), 11, &witness[..witness.len() - 1], &element),
+            ACC_ERR_LENGTH
+        );
+        assert_eq!(
+            verify(&root, (30, 0), 11, &witness[..32 * 3], &element),
+            ACC_ERR_WITNESS_TOO_SHORT
+        );
+        // the high half of `i` is not ignored
+        assert_ne!(verify(&root, (30, 1), 11, &witness, &element), ACC_OK);
+        let mut tampered = witness.clone();
+        tampered[0] ^= 1;
+        assert_eq!(
+            verify(&root, (30, 0), 11, &tampered, &element),
+            ACC_ERR_ROOT_MISMATCH
+        );
+        assert_eq!(
+            verify(&root, (30, 0), 31, &witness, &element),
+            ACC_ERR_OUT_OF_BOUNDS
+        );
+        let null = unsafe {
+            acc_verify_sha256(
+                std::ptr::null(),
+                30,
+                0,
+                11,
+                0,
+                witness.as_ptr(),
+                witness.len(),
+                element.as_ptr(),
+            )
+        };
+        assert_eq!(null, ACC_ERR_NULL);
+        let no_witness = unsafe {
+            acc_verify_sha256(
+                root.as_ptr(),
+                30,
+                0,
+                11,
+                0,
+                std::ptr::null(),
+                0,
+                element.as_ptr(),
+            )
+        };
+        assert_eq!(no_witness, ACC_ERR_WITNESS_TOO_SHORT);
+    }
+
+    #[test]
+    fn finds_nearest_retained_root() {
+        let mut prover: SimpleProver<sha2::Sha256> =
+            (0u8..60).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let full = prover.clone();
+        prover
+            .retain_for_proofs(50.into(), &[37.into(), 9.into()])
+            .unwrap();
+        for i in 0u8..=60 {
+            let nearest = (0..=i)
+                .rev()
+                .map(U256::from)
+                .find(|k| prover.get_r(k).is_some())
+                .unwrap();
+            assert_eq!(
+                prover.root_at_or_before(i),
+                Some((nearest, full.root_at(nearest).unwrap()))
+            );
+        }
+        assert_eq!(prover.root_at_or_before(50).unwrap().0, U256::from(50));
+        assert_eq!(prover.root_at_or_before(51).unwrap().0, U256::from(50));
+        let gap = (1u8..50).find(|k| prover.root_at(*k).is_none()).unwrap();
+        assert!(prover.root_at_or_before(gap).unwrap().0 < U256::from(gap));
+    }
+
+    #[test]
+    fn rejects_package_for_another_digest() {
+        type Trunc = sha2::Sha512Trunc256;
+        assert_eq!(SimpleProver::<sha2::Sha256>::digest_name(), "sha256");
+        assert_eq!(SimpleAccumulator::<Trunc>::digest_name(), "sha512/256");
+        assert_eq!(
+            DigestKind::from_name(SimpleProver::<Trunc>::digest_name()),
+            Some(DigestKind::Sha512Trunc256)
+        );
+        assert_eq!(SimpleProver::<sha2::Sha224>::digest_name(), "sha224");
+        assert_eq!(DigestKind::of::<sha2::Sha224>(), None);
+
+        // outputs of the same size, so only the name tells them apart
+        let prover: SimpleProver<Trunc> = (0u8..20).map(|i| Trunc::digest(&[i])).collect();
+        let package = prover.export_for_verifier();
+        let element = *prover.get_element(&7.into()).unwrap();
+        let witness = prover.prove(7).unwrap();
+        package.verify(&witness, 7.into(), &element).unwrap();
+
+        let as_sha256 = VerifierPackage::<sha2::Sha256> {
+            root: package.root,
+            size: package.size,
+            genesis: package.genesis,
+            digest: package.digest,
+        };
+        assert!(matches!(
+            as_sha256.verify(&witness, 7.into(), &element),
+            Err(ProverError::DigestMismatch)
+        ));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn proves_from_mmapped_history() {
+        use crate::MmapStore;
+
+        type Store = MmapStore<sha2::Sha256>;
+        let prover: SimpleProver<sha2::Sha256> =
+            (0u8..90).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let dir = std::env::temp_dir();
+        let path = |name: &str| dir.join(format!("accumulator-{}-{}", std::process::id(), name));
+        let (elements_path, r_path) = (path("elements"), path("r"));
+        Store::write(&elements_path, prover.elements()).unwrap();
+        Store::write(&r_path, prover.r()).unwrap();
+        let elements = Store::open(&elements_path).unwrap();
+        let r = Store::open(&r_path).unwrap();
+        assert_eq!(HistoryStore::len(&r), 91);
+        assert!(r.entries().eq(prover.r().entries()));
+
+        let root = prover.get_root();
+        for j in 1u8..=90 {
+            let witness = prove_from_stores(&elements, &r, 90.into(), j.into()).unwrap();
+            assert_eq!(witness, prover.prove_from(90, j).unwrap());
+            let element = elements.get(&j.into()).unwrap();
+            SimpleProver::<sha2::Sha256>::verify(&root, 90.into(), j.into(), &witness, element)
+                .unwrap();
+        }
+        assert!(prove_from_stores(&elements, &r, 91.into(), 3.into()).is_err());
+
+        let mut pruned = prover.clone();
+        pruned.retain_for_proofs(90.into(), &[3.into()]).unwrap();
+        assert!(Store::write(&r_path, pruned.r()).is_err());
+        std::fs::remove_file(elements_path).unwrap();
+        std::fs::remove_file(r_path).unwrap();
+    }
+
+    #[test]
+    fn verifies_suffix_from_checkpoint() {
+        type P = SimpleProver<sha2::Sha256>;
+        let prover: P = (0u8..60).map(|i| sha2::Sha256::digest(&[i])).collect();
+        let element = *prover.get_element(&5.into()).unwrap();
+        let full = prover.prove_from(60, 5).unwrap();
+        let indices = P::proof_chain_indices(60, 5).unwrap();
+        assert!(full.len() > 6);
+
+        for (n, m) in indices.iter().step_by(3).enumerate().skip(1) {
+            let suffix = &full[3 * n..];
+            let r_m = prover.root_at(*m).unwrap();
+            assert_eq!(suffix, &prover.prove_from(*m, 5).unwrap()[..]);
+            P::verify_from_checkpoint(&r_m, *m, 5.into(), suffix, &element).unwrap();
+
+            let other = prover.root_at(*m - U256::one()).unwrap();
+            assert!(P::verify_from_checkpoint(&other, *m, 5.into(), suffix, &element).is_err());
+            assert!(P::verify_from_checkpoint(&r_m, *m, 5.into(), &full, &element).is_err());
+        }
+    }
+
+    // a digest from outside the crate, named the way a user would name it
+    impl NamedDigest for sha3::Keccak256 {
+        const NAME: &'static str = "keccak256";
+    }
+
+    #[test]
+    fn rejects_keccak_witness_as_sha256() {
+        type Keccak = sha3::Keccak256;
+        let prover: SimpleProver<Keccak> = (0u8..20).map(|i| Keccak::digest(&[i])).collect();
+        let element = *prover.get_element(&7.into()).unwrap();
+        let envelope = WitnessEnvelope::<Keccak> {
+            i: prover.len(),
+            j: 7.into(),
+            nodes: prover.prove(7).unwrap(),
+        };
+        let bytes = envelope.encode();
+        let decoded = WitnessEnvelope::<Keccak>::decode(&bytes).unwrap();
+        SimpleProver::<Keccak>::verify(
+            &prover.get_root(),
+            decoded.i,
+            decoded.j,
+            &decoded.nodes,
+            &element,
+        )
+        .unwrap();
+        assert_eq!((decoded.i, decoded.j), (envelope.i, envelope.j));
+        assert_eq!(decoded.nodes, envelope.nodes);
+        assert!(matches!(
+            WitnessEnvelope::<sha2::Sha256>::decode(&bytes),
+            Err(ProverError::DigestMismatch)
+        ));
+        assert!(matches!(
+            WitnessEnvelope::<Keccak>::decode(&bytes[..4]),
+            Err(ProverError::MalformedWitness)
+        ));
+
+        let package = prover.export_for_verifier();
+        assert_eq!(package.digest, "keccak256");
+        let as_sha256 = VerifierPackage::<sha2::Sha256> {
+            root: package.root,
+            size: package.size,
+            genesis: package.genesis,
+            digest: package.digest,
+        };
+        assert!(matches!(
+            as_sha256.verify(&envelope.nodes, 7.into(), &element),
+            Err(ProverError::DigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn prove_defaults_to_current_root() {
+        let mut prover = SimpleProver::<sha2::Sha256>::default();
+        let elements: Vec<_> = (0u8..6).map(|i| sha2::Sha256::digest(&[i])).collect();
+        prover.insert_iter(elements.iter().copied());
+
+        // 6 inserts populate only 3 slots, so proving from `state_len()`
+        // would have been against a stale root
+        assert!(U256::from(prover.state_len()) < prover.len());
+
+        let root = prover.get_root();
+        for (j, element) in elements.iter().enumerate() {
+            let j = U256::from(j + 1);
+            let witness = prover.prove(j).unwrap();
+            SimpleProver::<sha2::Sha256>::verify(&root, prover.len(), j, &witness, element)
+                .unwrap();
+        }
+    }
 }
@@ -0,0 +1,158 @@
+//! Read access to a prover's history maps
+
+use std::collections::BTreeMap;
+#[cfg(feature = "fxhash")]
+use std::hash::{BuildHasher, Hash};
+
+use crate::{utils, Index, ProverError};
+
+/// The entries of a [`HistoryStore`], in index order
+pub type Entries<'a, I, E> = Box<dyn Iterator<Item = (I, &'a E)> + 'a>;
+
+/// A map from index to element or root. Proving only needs point lookups;
+/// ordered access is provided by `entries`, which may have to sort.
+pub trait HistoryStore<I, E> {
+    fn get(&self, i: &I) -> Option<&E>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All entries, in index order
+    fn entries(&self) -> Entries<'_, I, E>;
+
+    /// The entry with the largest index at or before `i`. This scans
+    /// `entries` unless the store can seek to it.
+    fn last_at_or_before(&self, i: &I) -> Option<(I, &E)>
+    where
+        I: Ord,
+    {
+        self.entries().take_while(|(k, _)| k <= i).last()
+    }
+}
+
+impl<I: Ord + Copy, E: Clone> HistoryStore<I, E> for BTreeMap<I, E> {
+    fn get(&self, i: &I) -> Option<&E> {
+        BTreeMap::get(self, i)
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn entries(&self) -> Entries<'_, I, E> {
+        Box::new(self.iter().map(|(i, e)| (*i, e)))
+    }
+
+    fn last_at_or_before(&self, i: &I) -> Option<(I, &E)> {
+        self.range(..=*i).next_back().map(|(k, e)| (*k, e))
+    }
+}
+
+#[cfg(feature = "fxhash")]
+impl<I: Ord + Hash + Copy, E: Clone, S: BuildHasher> HistoryStore<I, E>
+    for std::collections::HashMap<I, E, S>
+{
+    fn get(&self, i: &I) -> Option<&E> {
+        std::collections::HashMap::get(self, i)
+    }
+
+    fn len(&self) -> usize {
+        std::collections::HashMap::len(self)
+    }
+
+    fn entries(&self) -> Entries<'_, I, E> {
+        let mut entries: Vec<_> = self.iter().map(|(i, e)| (*i, e)).collect();
+        entries.sort_unstable_by_key(|(i, _)| *i);
+        Box::new(entries.into_iter())
+    }
+}
+
+/// A read-only history map storing each distinct value once, with indices
+/// pointing into a deduplicated list. Lookups and entries are the same as
+/// the map it was built from, so proofs read from it are unchanged.
+///
+/// Roots never repeat short of a hash collision, as each commits to the one
+/// before it, so this only saves space on element maps of repetitive data.
+#[derive(Clone, Debug)]
+pub struct Interned<I, E> {
+    slots: BTreeMap<I, usize>,
+    values: Vec<E>,
+}
+
+impl<I: Ord + Copy, E: Clone + Eq + std::hash::Hash> Interned<I, E> {
+    /// Intern every entry of `store`
+    pub fn from_store(store: &impl HistoryStore<I, E>) -> Self {
+        let mut seen = std::collections::HashMap::new();
+        let mut values = vec![];
+        let slots = store
+            .entries()
+            .map(|(i, e)| {
+                let slot = *seen.entry(e.clone()).or_insert_with(|| {
+                    values.push(e.clone());
+                    values.len() - 1
+                });
+                (i, slot)
+            })
+            .collect();
+        Self { slots, values }
+    }
+
+    /// The number of distinct values stored
+    pub fn unique_len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+impl<I: Ord + Copy, E: Clone> HistoryStore<I, E> for Interned<I, E> {
+    fn get(&self, i: &I) -> Option<&E> {
+        self.slots.get(i).map(|slot| &self.values[*slot])
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn entries(&self) -> Entries<'_, I, E> {
+        Box::new(
+            self.slots
+                .iter()
+                .map(move |(i, slot)| (*i, &self.values[*slot])),
+        )
+    }
+
+    fn last_at_or_before(&self, i: &I) -> Option<(I, &E)> {
+        let (k, slot) = self.slots.range(..=*i).next_back()?;
+        Some((*k, &self.values[*slot]))
+    }
+}
+
+/// Prove the element at `j` against the root at `i` from the `elements` and
+/// `r` history maps alone, without a prover around them. The witness is the
+/// one [`Prover::prove_from`](crate::Prover::prove_from) gives for the same
+/// history, and the errors are the same.
+pub fn prove_from_stores<I: Index, E: Clone>(
+    elements: &impl HistoryStore<I, E>,
+    r: &impl HistoryStore<I, E>,
+    i: I,
+    j: I,
+) -> Result<Vec<E>, ProverError> {
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    let missing = |n: I| ProverError::MissingHistory(n.into());
+    let mut witness = Vec::with_capacity(3 * utils::proof_steps(i, j));
+    let mut k = i;
+    loop {
+        let (prev_k, pred_k) = (k - I::one(), utils::pred(k));
+        witness.push(elements.get(&k).ok_or_else(|| missing(k))?.clone());
+        witness.push(r.get(&prev_k).ok_or_else(|| missing(prev_k))?.clone());
+        witness.push(r.get(&pred_k).ok_or_else(|| missing(pred_k))?.clone());
+        if k == j {
+            return Ok(witness);
+        }
+        k = utils::step(k, j).1;
+    }
+}
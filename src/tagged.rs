@@ -0,0 +1,69 @@
+//! A prover that keeps application metadata alongside each element
+
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+use std::collections::BTreeMap;
+
+use crate::{Accumulator, Index, SimpleProver};
+
+/// A [`SimpleProver`] that also records a tag, such as a timestamp or a
+/// sequence id, for each element inserted through it. Tags are auxiliary:
+/// they are not hashed, and proofs are over the elements only. The prover
+/// can be read, and proofs made, through the wrapper.
+#[derive(Clone, Debug)]
+pub struct TaggedProver<D, T, I = U256>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    prover: SimpleProver<D, I>,
+    tags: BTreeMap<I, T>,
+}
+
+impl<D, T, I> Default for TaggedProver<D, T, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    fn default() -> Self {
+        Self {
+            prover: Default::default(),
+            tags: Default::default(),
+        }
+    }
+}
+
+impl<D, T, I> TaggedProver<D, T, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    /// Insert `element` with its tag, returning the new root
+    pub fn insert_tagged(&mut self, element: &Output<D>, tag: T) -> Output<D> {
+        let root = self.prover.insert(element);
+        self.tags.insert(self.prover.len(), tag);
+        root
+    }
+
+    /// The tag of the element at `i`
+    pub fn tag_at(&self, i: impl Into<I>) -> Option<&T> {
+        self.tags.get(&i.into())
+    }
+
+    /// Unwrap into the prover and the tags by index
+    pub fn into_parts(self) -> (SimpleProver<D, I>, BTreeMap<I, T>) {
+        (self.prover, self.tags)
+    }
+}
+
+impl<D, T, I> std::ops::Deref for TaggedProver<D, T, I>
+where
+    D: Digest + Clone + Default,
+    I: Index,
+{
+    type Target = SimpleProver<D, I>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.prover
+    }
+}
@@ -1,9 +1,40 @@
-use ethers_core::types::U256;
+use crate::{Branch, Index};
 
-pub(crate) fn highest_divisor_power_of_2(n: U256) -> U256 {
-    n & (!(n - 1))
+pub(crate) fn highest_divisor_power_of_2<I: Index>(n: I) -> I {
+    n & (!(n - I::one()))
 }
 
-pub(crate) fn pred(n: U256) -> U256 {
+pub(crate) fn pred<I: Index>(n: I) -> I {
     n - highest_divisor_power_of_2(n)
 }
+
+/// The branch a proof takes from `i` towards `j`, and the index it leads to.
+/// Requires `i > j`.
+pub(crate) fn step<I: Index>(i: I, j: I) -> (Branch, I) {
+    let pred_i = pred(i);
+    if pred_i >= j {
+        (Branch::Pred, pred_i)
+    } else {
+        (Branch::Prev, i - I::one())
+    }
+}
+
+/// The branches a proof of `j` against `i` takes. Empty if `j >= i`.
+pub(crate) fn branches<I: Index>(mut i: I, j: I) -> Vec<Branch> {
+    let mut result = vec![];
+    while i > j {
+        let (branch, next) = step(i, j);
+        result.push(branch);
+        i = next;
+    }
+    result
+}
+
+/// The number of `(x, prev, pred)` triples in a proof of `j` against `i`.
+/// Returns 0 if `j > i`.
+pub(crate) fn proof_steps<I: Index>(i: I, j: I) -> usize {
+    if j > i {
+        return 0;
+    }
+    branches(i, j).len() + 1
+}
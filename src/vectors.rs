@@ -0,0 +1,117 @@
+//! Known-answer vectors for the SHA-256 accumulator. Element `n` is
+//! `sha256("element-n")`. Each vector records the root after `size` inserts,
+//! and the witness proving element `j` against that root. Any change to the
+//! digest chaining will break these.
+
+use crate::*;
+use digest::Digest;
+
+struct Vector {
+    size: u64,
+    root: &'static str,
+    j: u64,
+    witness: &'static [&'static str],
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        size: 1,
+        root: "5da753f94e97cfb7e5cf5648eed9db7124ac085b47014a6c636acf60896156b0",
+        j: 1,
+        witness: &[
+            "79c50ca6a294526a587ac2f93e2fa8a26f68d0112da27c0050edead60a95e973",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ],
+    },
+    Vector {
+        size: 4,
+        root: "7b127117b095167bf7f6a597d09f1ed25a7dc3645bb5302521a9106c9ac819f6",
+        j: 2,
+        witness: &[
+            "08128ac396bd5752834b0de8ec454325f48ab44a0cc65627f6b31c0630ed0ff6",
+            "fd549959f7814d699a825358b0940eef239371f8b3bed6edd5a73b2e827e25f0",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "beb056842d73acfa300a12493c5b48f87010ef0522d4f1f6203d69a93c2eb62a",
+            "25b79bbcb875765195876c2258b31ccafb1a4979660033a5f9fee6ccca8cf701",
+            "25b79bbcb875765195876c2258b31ccafb1a4979660033a5f9fee6ccca8cf701",
+            "d46ac69d8280eacd76682d5bda6b1b3b8ff0899e6a7907470e04d92031724449",
+            "5da753f94e97cfb7e5cf5648eed9db7124ac085b47014a6c636acf60896156b0",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ],
+    },
+    Vector {
+        size: 7,
+        root: "b565f1feae4aeb5b131e944c01b9aae06de46fe5c3ddcfad3df21880b69e3bc3",
+        j: 4,
+        witness: &[
+            "5f7285750bef11a37ca4950230d60767445dc6a1e1c62a3d456f0a6c6b4a585c",
+            "70f71e518f248ae7263e8e53962dd6bbc7bb8940e9727982eb9192511c6475b0",
+            "70f71e518f248ae7263e8e53962dd6bbc7bb8940e9727982eb9192511c6475b0",
+            "83119b71d8b8e30a1190752265553b41acd5c3b11dfacc0721ac96111895891b",
+            "805e9535b80722b8914344d1d6c68c6b7f568aeaeccf398d7cb6bc1f7df96688",
+            "7b127117b095167bf7f6a597d09f1ed25a7dc3645bb5302521a9106c9ac819f6",
+            "08128ac396bd5752834b0de8ec454325f48ab44a0cc65627f6b31c0630ed0ff6",
+            "fd549959f7814d699a825358b0940eef239371f8b3bed6edd5a73b2e827e25f0",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ],
+    },
+    Vector {
+        size: 16,
+        root: "26046b7fa75fb6968372848cc627e6b834c0b4e1c93f89d5841d5dc46e83611e",
+        j: 8,
+        witness: &[
+            "2b7ca604e4f940b52a667bec61c3e8c999d0aabda62ee202064c353b8faa6d51",
+            "4db24199f17338d1279754e823c440432a9f71daf093dd86b815b9c9ecc251e7",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "de59696a6a7f0cdabb53772550c72997a0b7b5c96f000cd71b09611327d67164",
+            "d7bea30a74973a20b8d45144e59c4d6e8d6f78350093dcb0bd3e47e24c46e1dc",
+            "d7bea30a74973a20b8d45144e59c4d6e8d6f78350093dcb0bd3e47e24c46e1dc",
+            "640b9cdd80055c1fb3077ead9fd66567228868a0e29af774a474820744b54eff",
+            "394fd0187d0eacec425e2ad8b5c9c32cf42efbd760108eba3fd74e2b57f5d88e",
+            "0b82b42b1319f278c84ec3bb46c36ec1160628bcacfd56f7be37acd61b65ef17",
+            "fea9285749acea809f1bb11ad324013f9037d7d8d03747277b16ff53f5afc2c7",
+            "1e94c98c8e925f27d0716069063659ecbf98d743606272af04c3c8227d8acae0",
+            "fcf1c2bad02047faf9341af267795b0c57e56f5f55df1fd53f39e79a12010508",
+            "95c41007699620618ed64eebf18dadd4a681ff0c609f53d407f33c034ff5f435",
+            "b565f1feae4aeb5b131e944c01b9aae06de46fe5c3ddcfad3df21880b69e3bc3",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ],
+    },
+];
+
+fn build(size: u64) -> SimpleProver<sha2::Sha256> {
+    let mut prover = SimpleProver::default();
+    for n in 1..=size {
+        prover.insert_data(format!("element-{}", n));
+    }
+    prover
+}
+
+#[test]
+fn roots_match_vectors() {
+    for v in VECTORS {
+        let prover = build(v.size);
+        assert_eq!(hex::encode(prover.get_root()), v.root, "size {}", v.size);
+    }
+}
+
+#[test]
+fn witnesses_match_vectors() {
+    for v in VECTORS {
+        let prover = build(v.size);
+        let witness = prover.prove_from(v.size, v.j).unwrap();
+        let encoded: Vec<_> = witness.iter().map(hex::encode).collect();
+        assert_eq!(encoded, v.witness, "size {}", v.size);
+
+        let element = sha2::Sha256::digest(format!("element-{}", v.j).as_bytes());
+        SimpleProver::<sha2::Sha256>::verify(
+            &prover.get_root(),
+            v.size.into(),
+            v.j.into(),
+            &witness,
+            &element,
+        )
+        .unwrap();
+    }
+}
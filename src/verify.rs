@@ -0,0 +1,436 @@
+//! Stateless verification of accumulator proofs
+
+use digest::{Digest, Output};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use ethers_core::types::U256;
+
+use crate::{utils, Branch, Index, ProverError};
+
+/// The outcome of checking one triple of a witness
+pub(crate) enum Step<'w, D: Digest> {
+    /// The triple checked out, and the walk follows this branch
+    Followed(Branch),
+    /// The triple at `j` checked out
+    Done(&'w [Output<D>; 3]),
+}
+
+/// A walk down a witness, one triple at a time. Nodes are only ever
+/// borrowed from the witness, never copied.
+pub(crate) struct Walk<'w, D: Digest, I> {
+    expected: &'w Output<D>,
+    genesis: Output<D>,
+    i: I,
+    j: I,
+    witness: &'w [Output<D>],
+    offset: usize,
+}
+
+impl<'w, D: Digest, I: Index> Walk<'w, D, I> {
+    pub(crate) fn new(
+        r_i: &'w Output<D>,
+        genesis: &Output<D>,
+        i: I,
+        j: I,
+        witness: &'w [Output<D>],
+    ) -> Self {
+        Self {
+            expected: r_i,
+            genesis: genesis.clone(),
+            i,
+            j,
+            witness,
+            offset: 0,
+        }
+    }
+
+    /// Check that the next `(x, prev, pred)` triple hashes to the root
+    /// expected at its index
+    pub(crate) fn advance(&mut self) -> Result<Step<'w, D>, ProverError> {
+        let (i, j) = (self.i, self.j);
+        if j > i || j.is_zero() {
+            return Err(ProverError::OutOfBounds);
+        }
+        // Every index we get here is at least `j`, so at least 1, and must
+        // hold a real root rather than the sentinel's
+        if *self.expected == self.genesis {
+            return Err(ProverError::UnexpectedEmptyRoot);
+        }
+
+        let triple =
+            triple_at::<D>(self.witness, self.offset).ok_or(ProverError::WitnessTooShort)?;
+        if hash_triple::<D>(triple) != *self.expected {
+            return Err(ProverError::RiMismatch(self.offset / 3));
+        }
+        if i == j {
+            return Ok(Step::Done(triple));
+        }
+
+        let (branch, next) = utils::step(i, j);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(%i, ?branch, "verified step");
+        self.expected = match branch {
+            Branch::Pred => &triple[2],
+            Branch::Prev => &triple[1],
+        };
+        self.i = next;
+        self.offset += 3;
+        Ok(Step::Followed(branch))
+    }
+}
+
+/// The triple starting `offset` nodes into `witness`, if there is one
+pub(crate) fn triple_at<D: Digest>(
+    witness: &[Output<D>],
+    offset: usize,
+) -> Option<&[Output<D>; 3]> {
+    witness.get(offset..)?.get(..3)?.try_into().ok()
+}
+
+/// The root committed to by an `(x, prev, pred)` triple
+pub(crate) fn hash_triple<D: Digest>(triple: &[Output<D>; 3]) -> Output<D> {
+    hash_nodes::<D>(&triple[0], &triple[1], &triple[2])
+}
+
+fn hash_nodes<D: Digest>(x: &Output<D>, prev: &Output<D>, pred: &Output<D>) -> Output<D> {
+    D::new().chain(x).chain(prev).chain(pred).finalize()
+}
+
+/// [`walk`] over a witness stored as three columns, the `x`, `prev` and
+/// `pred` of each triple, which must be the same length. Returns the
+/// position of the triple at `j`. Errors match `walk` on the interleaved
+/// witness, except that columns of different lengths are
+/// `MalformedWitness`.
+pub(crate) fn walk_columns<D: Digest, I: Index>(
+    r_i: &Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    j: I,
+    x: &[Output<D>],
+    prev: &[Output<D>],
+    pred: &[Output<D>],
+) -> Result<usize, ProverError> {
+    if x.len() != prev.len() || x.len() != pred.len() {
+        return Err(ProverError::MalformedWitness);
+    }
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    let (mut expected, mut i, mut n) = (r_i, i, 0);
+    loop {
+        if expected == genesis {
+            return Err(ProverError::UnexpectedEmptyRoot);
+        }
+        if n >= x.len() {
+            return Err(ProverError::WitnessTooShort);
+        }
+        if hash_nodes::<D>(&x[n], &prev[n], &pred[n]) != *expected {
+            return Err(ProverError::RiMismatch(n));
+        }
+        if i == j {
+            break;
+        }
+        let (branch, next) = utils::step(i, j);
+        expected = match branch {
+            Branch::Pred => &pred[n],
+            Branch::Prev => &prev[n],
+        };
+        i = next;
+        n += 1;
+    }
+    if x.len() != n + 1 {
+        return Err(ProverError::WitnessTooLong);
+    }
+    Ok(n)
+}
+
+/// Walk `witness` from `r_i` at index `i` down to `j`, checking that each
+/// `(x, prev, pred)` triple hashes to the root expected at its index, and
+/// that none of those roots is `genesis`, the root at index 0. `visit` is
+/// called with each branch taken. Returns the triple at `j`. Fails with
+/// `WitnessTooLong` if there are nodes after it.
+pub(crate) fn walk<'w, D: Digest, I: Index>(
+    r_i: &'w Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    j: I,
+    witness: &'w [Output<D>],
+    visit: impl FnMut(Branch),
+) -> Result<&'w [Output<D>; 3], ProverError> {
+    let triple = walk_prefix::<D, I>(r_i, genesis, i, j, witness, visit)?;
+    if witness.len() != 3 * utils::proof_steps(i, j) {
+        return Err(ProverError::WitnessTooLong);
+    }
+    Ok(triple)
+}
+
+/// [`walk`], ignoring any nodes after the triple at `j`
+pub(crate) fn walk_prefix<'w, D: Digest, I: Index>(
+    r_i: &'w Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    j: I,
+    witness: &'w [Output<D>],
+    mut visit: impl FnMut(Branch),
+) -> Result<&'w [Output<D>; 3], ProverError> {
+    let mut walk = Walk::<D, I>::new(r_i, genesis, i, j, witness);
+    loop {
+        match walk.advance()? {
+            Step::Followed(branch) => visit(branch),
+            Step::Done(triple) => return Ok(triple),
+        }
+    }
+}
+
+/// Walk `witness` from `r_i` at index `i` down to `j`, stopping short of
+/// the triple at `j`. Returns the root the walk arrives at, which is `r_i`
+/// itself if `i == j`. Fails with `WitnessTooLong` if there are nodes left.
+pub(crate) fn walk_to_root<'w, D: Digest, I: Index>(
+    r_i: &'w Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    j: I,
+    witness: &'w [Output<D>],
+) -> Result<&'w Output<D>, ProverError> {
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    let mut walk = Walk::<D, I>::new(r_i, genesis, i, j, witness);
+    while walk.i != j {
+        walk.advance()?;
+    }
+    if witness.len() != walk.offset {
+        return Err(ProverError::WitnessTooLong);
+    }
+    Ok(walk.expected)
+}
+
+/// The triples already hashed by [`walk_shared`], by index, with the root
+/// each one was checked against
+type Verified<'w, D, I> = BTreeMap<I, (&'w Output<D>, &'w [Output<D>; 3])>;
+
+/// An index, the element claimed there, and its witness
+pub(crate) type Item<D, I> = (I, Output<D>, Vec<Output<D>>);
+
+/// Verify several `(j, element, witness)` items against the same `r_i`,
+/// hashing each triple only the first time it is seen at its index. A later
+/// witness holding the same triple, expected to hash to the same root, is
+/// checked by comparison instead. Returns the result for each item, which
+/// matches `walk` followed by `check_element`, and the digests computed.
+pub(crate) fn walk_shared<D: Digest, I: Index>(
+    r_i: &Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    items: &[Item<D, I>],
+) -> (Vec<Result<(), ProverError>>, usize) {
+    let mut verified = Verified::<D, I>::new();
+    let mut hashes = 0;
+    let results = items
+        .iter()
+        .map(|(j, element, witness)| {
+            walk_memo::<D, I>(r_i, genesis, i, *j, witness, &mut verified, &mut hashes)
+                .and_then(|triple| check_element::<D>(triple, element))
+        })
+        .collect();
+    (results, hashes)
+}
+
+fn walk_memo<'w, D: Digest, I: Index>(
+    r_i: &'w Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    j: I,
+    witness: &'w [Output<D>],
+    verified: &mut Verified<'w, D, I>,
+    hashes: &mut usize,
+) -> Result<&'w [Output<D>; 3], ProverError> {
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    let (mut expected, mut k, mut offset) = (r_i, i, 0);
+    let triple = loop {
+        if expected == genesis {
+            return Err(ProverError::UnexpectedEmptyRoot);
+        }
+        let triple = triple_at::<D>(witness, offset).ok_or(ProverError::WitnessTooShort)?;
+        if verified.get(&k) != Some(&(expected, triple)) {
+            *hashes += 1;
+            if hash_triple::<D>(triple) != *expected {
+                return Err(ProverError::RiMismatch(offset / 3));
+            }
+            verified.insert(k, (expected, triple));
+        }
+        if k == j {
+            break triple;
+        }
+        let (branch, next) = utils::step(k, j);
+        expected = match branch {
+            Branch::Pred => &triple[2],
+            Branch::Prev => &triple[1],
+        };
+        k = next;
+        offset += 3;
+    };
+    if witness.len() != 3 * utils::proof_steps(i, j) {
+        return Err(ProverError::WitnessTooLong);
+    }
+    Ok(triple)
+}
+
+/// The number of nodes in a witness proving `j` against `i`, from the
+/// indices alone. A verifier can use this to size or reject a witness before
+/// reading it. Fails with `OutOfBounds` where proving would.
+pub fn expected_witness_len<I: Index>(i: I, j: I) -> Result<usize, ProverError> {
+    if j > i || j.is_zero() {
+        return Err(ProverError::OutOfBounds);
+    }
+    Ok(3 * utils::proof_steps(i, j))
+}
+
+/// The most trailing nodes [`Prover::verify_lenient`] ignores: one
+/// triple's worth
+///
+/// [`Prover::verify_lenient`]: crate::Prover::verify_lenient
+pub const LENIENT_TRAILING_NODES: usize = 3;
+
+/// Walk a range proof of `lo..=hi` against `r_i`, starting `start` nodes
+/// into `witness`. The proven elements are pushed onto `proven` from `hi`
+/// down. Returns the offset just past the range, and the root at `lo - 1`
+/// committed to by the triple at `lo`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn walk_slice<'w, D: Digest, I: Index>(
+    r_i: &'w Output<D>,
+    genesis: &Output<D>,
+    i: I,
+    lo: I,
+    hi: I,
+    witness: &'w [Output<D>],
+    start: usize,
+    proven: &mut Vec<(I, Output<D>)>,
+) -> Result<(usize, Output<D>), ProverError> {
+    if lo > hi {
+        return Err(ProverError::OutOfBounds);
+    }
+    let rest = witness.get(start..).ok_or(ProverError::WitnessTooShort)?;
+    let triple = walk_prefix::<D, I>(r_i, genesis, i, hi, rest, |_| {}).map_err(|e| match e {
+        ProverError::RiMismatch(n) => ProverError::RiMismatch(n + start / 3),
+        e => e,
+    })?;
+    proven.push((hi, triple[0].clone()));
+    let mut expected = &triple[1];
+    let mut offset = start + 3 * utils::proof_steps(i, hi);
+    let mut k = hi;
+    while k > lo {
+        k = k - I::one();
+        if expected == genesis {
+            return Err(ProverError::UnexpectedEmptyRoot);
+        }
+        let triple = triple_at::<D>(witness, offset).ok_or(ProverError::WitnessTooShort)?;
+        if hash_triple::<D>(triple) != *expected {
+            return Err(ProverError::RiMismatch(offset / 3));
+        }
+        proven.push((k, triple[0].clone()));
+        expected = &triple[1];
+        offset += 3;
+    }
+    Ok((offset, expected.clone()))
+}
+
+/// Verification that can be spread over several calls, checking a bounded
+/// number of triples per [`VerifyJob::step`]. This lets a caller that must
+/// stay responsive, like a browser worker, yield between chunks. The final
+/// result is the same as [`Prover::verify`](crate::Prover::verify).
+pub struct VerifyJob<'w, D: Digest, I = U256> {
+    walk: Walk<'w, D, I>,
+    element: &'w Output<D>,
+    per_step: usize,
+    checked: usize,
+    total: usize,
+    result: Option<Result<(), ProverError>>,
+}
+
+impl<'w, D: Digest, I: Index> VerifyJob<'w, D, I> {
+    /// Prepare to verify `element` at `j` against `r_i`, checking at most
+    /// `per_step` triples per call to `step`. A `per_step` of 0 is treated
+    /// as 1. The root at index 0 is taken to be all zeros, unless set by
+    /// `with_genesis`.
+    pub fn new(
+        r_i: &'w Output<D>,
+        i: I,
+        j: I,
+        witness: &'w [Output<D>],
+        element: &'w Output<D>,
+        per_step: usize,
+    ) -> Self {
+        Self {
+            walk: Walk::new(r_i, &Default::default(), i, j, witness),
+            element,
+            per_step: per_step.max(1),
+            checked: 0,
+            total: utils::proof_steps(i, j),
+            result: None,
+        }
+    }
+
+    /// Verify against a log whose root at index 0 is `genesis`, as for
+    /// [`Prover::verify_with_genesis`](crate::Prover::verify_with_genesis)
+    pub fn with_genesis(mut self, genesis: &Output<D>) -> Self {
+        self.walk.genesis = genesis.clone();
+        self
+    }
+
+    /// Check up to `per_step` more triples. Returns `None` while there is
+    /// more work to do, and the result once verification has finished.
+    pub fn step(&mut self) -> Option<Result<(), ProverError>> {
+        if self.result.is_some() {
+            return self.result;
+        }
+        for _ in 0..self.per_step {
+            let result = match self.walk.advance() {
+                Ok(Step::Followed(_)) => {
+                    self.checked += 1;
+                    continue;
+                }
+                Ok(Step::Done(triple)) => {
+                    self.checked += 1;
+                    if self.walk.witness.len() != 3 * self.total {
+                        Err(ProverError::WitnessTooLong)
+                    } else {
+                        check_element::<D>(triple, self.element)
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            self.result = Some(result);
+            break;
+        }
+        self.result
+    }
+
+    /// The fraction of the work done, from 0.0 to 1.0
+    pub fn progress(&self) -> f64 {
+        if self.result.is_some() || self.total == 0 {
+            1.0
+        } else {
+            self.checked as f64 / self.total as f64
+        }
+    }
+
+    /// The result, if verification has finished
+    pub fn result(&self) -> Option<Result<(), ProverError>> {
+        self.result
+    }
+}
+
+/// Check that the final triple of a walk commits to `element`
+pub(crate) fn check_element<D: Digest>(
+    triple: &[Output<D>; 3],
+    element: &Output<D>,
+) -> Result<(), ProverError> {
+    if triple[0] == *element {
+        Ok(())
+    } else {
+        Err(ProverError::XiMismatch)
+    }
+}
@@ -0,0 +1,63 @@
+//! Build-time check that stateless verification has no panicking paths.
+//!
+//! `#[no_panic]` makes linking fail if the optimizer can't remove every path
+//! to a panic from the annotated function, so this target fails to build if
+//! one is reintroduced into `verify`. That needs optimizations, so debug
+//! builds only run the hostile inputs below, without the annotation. Run the
+//! full check with:
+//!
+//! ```text
+//! cargo test --release --features no-panic --test no_panic
+//! ```
+//!
+//! Each index type gets its own wrapper, as the check is made per
+//! instantiation.
+
+use bigspider_accumulator::{Accumulator, Prover, ProverError, SimpleProver};
+use digest::{Digest, Output};
+use ethers_core::types::U256;
+#[cfg(not(debug_assertions))]
+use no_panic::no_panic;
+use sha2::Sha256;
+
+macro_rules! no_panic_verify {
+    ($($name:ident: $t:ty),*) => {$(
+        mod $name {
+            use super::*;
+
+            type P = SimpleProver<Sha256, $t>;
+
+            #[cfg_attr(not(debug_assertions), no_panic)]
+            fn verify(
+                r_i: &Output<Sha256>,
+                i: $t,
+                j: $t,
+                witness: &[Output<Sha256>],
+                element: &Output<Sha256>,
+            ) -> Result<(), ProverError> {
+                P::verify(r_i, i, j, witness, element)
+            }
+
+            #[test]
+            fn verify_is_panic_free() {
+                let prover: P = (0u8..20).map(|i| Sha256::digest(&[i])).collect();
+                let root = prover.get_root();
+                let (five, twenty) = (<$t>::from(5u8), <$t>::from(20u8));
+                let element = *prover.get_element(&five).unwrap();
+                let witness = prover.prove(five).unwrap();
+                assert!(verify(&root, twenty, five, &witness, &element).is_ok());
+
+                // hostile inputs are rejected, not panicked on
+                let (zero, max) = (<$t>::from(0u8), <$t>::MAX);
+                assert!(verify(&root, twenty, five, &witness[..4], &element).is_err());
+                assert!(verify(&root, five, twenty, &witness, &element).is_err());
+                assert!(verify(&root, twenty, zero, &witness, &element).is_err());
+                assert!(verify(&root, max, zero, &witness, &element).is_err());
+                assert!(verify(&root, max, five, &witness, &element).is_err());
+                assert!(verify(&root, max, max, &[], &element).is_err());
+            }
+        }
+    )*}
+}
+
+no_panic_verify!(u256_index: U256, u64_index: u64, u128_index: u128);